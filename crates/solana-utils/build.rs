@@ -18,10 +18,7 @@ fn main() {
 }
 
 fn rustc_minor_version() -> Option<u32> {
-    let rustc = match env::var_os("RUSTC") {
-        Some(rustc) => rustc,
-        None => return None,
-    };
+    let rustc = env::var_os("RUSTC")?;
 
     let output = match Command::new(rustc).arg("--version").output() {
         Ok(output) => output,
@@ -38,10 +35,7 @@ fn rustc_minor_version() -> Option<u32> {
         return None;
     }
 
-    let next = match pieces.next() {
-        Some(next) => next,
-        None => return None,
-    };
+    let next = pieces.next()?;
 
     next.parse::<u32>().ok()
 }
@@ -0,0 +1,269 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{DeriveInput, Error, Expr, GenericArgument, PathArguments, Result, Type};
+
+use crate::ast::{Field, Input, Struct};
+
+pub fn derive(input: &DeriveInput) -> TokenStream {
+    let msg = match Input::from_syn(input) {
+        Input::Struct(input) => {
+            return impl_struct(input).unwrap_or_else(Error::into_compile_error);
+        }
+        Input::Enum(_) => "this trait cannot be derived for enums",
+        Input::Union(_) => "this trait cannot be derived for unions",
+    };
+    Error::new(Span::call_site(), msg).to_compile_error()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// `&'a AccountInfo<'info>`
+    Required,
+    /// `Option<&'a AccountInfo<'info>>`
+    Optional,
+    /// `&'a [AccountInfo<'info>]`, which receives the remaining accounts.
+    Remaining,
+}
+
+#[derive(Default)]
+struct Constraints {
+    signer: Option<Span>,
+    writable: Option<Span>,
+    executable: Option<Span>,
+    owner: Option<Expr>,
+    address: Option<Expr>,
+}
+
+fn impl_struct(input: Struct) -> Result<TokenStream> {
+    let lifetimes: Vec<_> = input.generics.lifetimes().map(|param| &param.lifetime).collect();
+    let (a, info) = match lifetimes.as_slice() {
+        [a] => (*a, *a),
+        [a, info] => (*a, *info),
+        _ => {
+            return Err(Error::new(
+                input.generics.span(),
+                "expected one or two lifetime parameters, for the accounts slice and `AccountInfo`",
+            ));
+        }
+    };
+
+    let mut statements = Vec::with_capacity(input.fields.len());
+    let mut bindings = Vec::with_capacity(input.fields.len());
+
+    for (i, field) in input.fields.iter().enumerate() {
+        let kind = field_kind(field.ty)?;
+        let constraints = constraints(field)?;
+        let name = match &field.original.ident {
+            Some(ident) => ident.to_string(),
+            None => i.to_string(),
+        };
+        let binding = format_ident!("__account{}", i);
+
+        if kind == Kind::Remaining {
+            if i + 1 != input.fields.len() {
+                return Err(Error::new(
+                    field.ty.span(),
+                    "the remaining accounts must be the last field",
+                ));
+            }
+            if let Some(span) = [constraints.signer, constraints.writable, constraints.executable]
+                .into_iter()
+                .flatten()
+                .chain(constraints.owner.as_ref().map(Spanned::span))
+                .chain(constraints.address.as_ref().map(Spanned::span))
+                .next()
+            {
+                return Err(Error::new(span, "the remaining accounts cannot have constraints"));
+            }
+
+            statements.push(quote! {
+                let #binding = accounts;
+            });
+            bindings.push((&field.member, binding));
+            continue;
+        }
+
+        let checks = checks(&constraints, &binding, &name);
+
+        statements.push(match kind {
+            Kind::Required => quote! {
+                let #binding = match accounts.split_first() {
+                    ::core::option::Option::Some((account, rest)) => {
+                        accounts = rest;
+                        account
+                    }
+                    ::core::option::Option::None => {
+                        return ::core::result::Result::Err(
+                            ::solana_utils::accounts::__private::missing_account(#name),
+                        );
+                    }
+                };
+                #(#checks)*
+            },
+            Kind::Optional => quote! {
+                let #binding = match accounts.split_first() {
+                    ::core::option::Option::Some((account, rest)) => {
+                        accounts = rest;
+                        if ::solana_utils::pubkeys_eq(account.key, program_id) {
+                            ::core::option::Option::None
+                        } else {
+                            ::core::option::Option::Some(account)
+                        }
+                    }
+                    ::core::option::Option::None => ::core::option::Option::None,
+                };
+                if let ::core::option::Option::Some(#binding) = #binding {
+                    #(#checks)*
+                }
+            },
+            Kind::Remaining => unreachable!(),
+        });
+        bindings.push((&field.member, binding));
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (members, bindings): (Vec<_>, Vec<_>) = bindings.into_iter().unzip();
+
+    Ok(quote! {
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::solana_utils::accounts::Accounts<#a, #info> for #ty #ty_generics
+            #where_clause
+        {
+            #[allow(unused_mut, unused_variables)]
+            fn try_accounts(
+                program_id: &::solana_utils::accounts::__private::Pubkey,
+                accounts: &#a [::solana_utils::accounts::__private::AccountInfo<#info>],
+            ) -> ::core::result::Result<Self, ::solana_utils::accounts::__private::ProgramError> {
+                let mut accounts = accounts;
+
+                #(#statements)*
+
+                ::core::result::Result::Ok(#ty { #(#members: #bindings,)* })
+            }
+        }
+    })
+}
+
+fn checks(constraints: &Constraints, binding: &syn::Ident, name: &str) -> Vec<TokenStream> {
+    let mut checks = Vec::new();
+
+    if let Some(span) = constraints.signer {
+        checks.push(quote_spanned! { span =>
+            ::solana_utils::accounts::__private::check_signer(#binding, #name)?;
+        });
+    }
+    if let Some(span) = constraints.writable {
+        checks.push(quote_spanned! { span =>
+            ::solana_utils::accounts::__private::check_writable(#binding, #name)?;
+        });
+    }
+    if let Some(span) = constraints.executable {
+        checks.push(quote_spanned! { span =>
+            ::solana_utils::accounts::__private::check_executable(#binding, #name)?;
+        });
+    }
+    if let Some(owner) = &constraints.owner {
+        checks.push(quote_spanned! { owner.span() =>
+            ::solana_utils::accounts::__private::check_owner(#binding, &(#owner), #name)?;
+        });
+    }
+    if let Some(address) = &constraints.address {
+        checks.push(quote_spanned! { address.span() =>
+            ::solana_utils::accounts::__private::check_address(#binding, &(#address), #name)?;
+        });
+    }
+
+    checks
+}
+
+fn constraints(field: &Field) -> Result<Constraints> {
+    let mut constraints = Constraints::default();
+
+    for attr in field.original.attrs.iter().filter(|attr| attr.path().is_ident("account")) {
+        attr.parse_nested_meta(|meta| {
+            let flag = if meta.path.is_ident("signer") {
+                &mut constraints.signer
+            } else if meta.path.is_ident("writable") {
+                &mut constraints.writable
+            } else if meta.path.is_ident("executable") {
+                &mut constraints.executable
+            } else {
+                let expr = if meta.path.is_ident("owner") {
+                    &mut constraints.owner
+                } else if meta.path.is_ident("address") {
+                    &mut constraints.address
+                } else {
+                    return Err(meta.error(
+                        "unsupported attribute, expected `signer`, `writable`, `executable`, \
+                         `owner` or `address`",
+                    ));
+                };
+
+                if expr.is_some() {
+                    return Err(meta.error("constraint specified more than once"));
+                }
+                *expr = Some(meta.value()?.parse()?);
+                return Ok(());
+            };
+
+            if flag.is_some() {
+                return Err(meta.error("constraint specified more than once"));
+            }
+            *flag = Some(meta.path.span());
+            Ok(())
+        })?;
+    }
+
+    Ok(constraints)
+}
+
+/// Returns the kind of a field, which must be a reference to an `AccountInfo`, an optional one or
+/// a slice of them.
+fn field_kind(ty: &Type) -> Result<Kind> {
+    let kind = match ty {
+        Type::Reference(reference) => match &*reference.elem {
+            Type::Slice(slice) if is_account_info(&slice.elem) => Some(Kind::Remaining),
+            elem if is_account_info(elem) => Some(Kind::Required),
+            _ => None,
+        },
+        Type::Path(ty) if ty.qself.is_none() => match ty.path.segments.last() {
+            Some(segment) if segment.ident == "Option" => match &segment.arguments {
+                PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+                    match args.args.first() {
+                        Some(GenericArgument::Type(Type::Reference(reference)))
+                            if is_account_info(&reference.elem) =>
+                        {
+                            Some(Kind::Optional)
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+        Type::Group(group) => return field_kind(&group.elem),
+        _ => None,
+    };
+
+    kind.ok_or_else(|| {
+        Error::new_spanned(
+            ty,
+            "unsupported field type, expected `&AccountInfo`, `Option<&AccountInfo>` or \
+             `&[AccountInfo]`",
+        )
+    })
+}
+
+fn is_account_info(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) if ty.qself.is_none() => {
+            ty.path.segments.last().is_some_and(|segment| segment.ident == "AccountInfo")
+        }
+        Type::Group(group) => is_account_info(&group.elem),
+        _ => false,
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
 use proc_macro2::{Ident, Span};
-use syn::{Data, DataEnum, DataStruct, DataUnion, DeriveInput, Generics, Index, Member, Type};
+use syn::{
+    Attribute, Data, DataEnum, DataStruct, DataUnion, DeriveInput, Generics, Index, Member, Type,
+};
 
 pub enum Input<'a> {
     Struct(Struct<'a>),
@@ -10,12 +12,14 @@ pub enum Input<'a> {
 }
 
 pub struct Struct<'a> {
+    pub attrs: &'a [Attribute],
     pub ident: Ident,
     pub generics: &'a Generics,
     pub fields: Vec<Field<'a>>,
 }
 
 pub struct Enum<'a> {
+    pub attrs: &'a [Attribute],
     pub ident: Ident,
     pub generics: &'a Generics,
     pub variants: Vec<Variant<'a>>,
@@ -28,6 +32,7 @@ pub struct Union<'a> {
 
 pub struct Variant<'a> {
     pub original: &'a syn::Variant,
+    pub attrs: &'a [Attribute],
     pub ident: Ident,
     pub fields: Vec<Field<'a>>,
 }
@@ -51,6 +56,7 @@ impl<'a> Input<'a> {
 impl<'a> Struct<'a> {
     fn from_syn(node: &'a DeriveInput, data: &'a DataStruct) -> Self {
         Struct {
+            attrs: &node.attrs,
             ident: node.ident.clone(),
             generics: &node.generics,
             fields: Field::multiple_from_syn(&data.fields, Span::call_site()),
@@ -63,7 +69,7 @@ impl<'a> Enum<'a> {
         let span = Span::call_site();
         let variants = data.variants.iter().map(|node| Variant::from_syn(node, span)).collect();
 
-        Enum { ident: node.ident.clone(), generics: &node.generics, variants }
+        Enum { attrs: &node.attrs, ident: node.ident.clone(), generics: &node.generics, variants }
     }
 }
 
@@ -80,6 +86,7 @@ impl<'a> Variant<'a> {
     fn from_syn(node: &'a syn::Variant, span: Span) -> Self {
         Variant {
             original: node,
+            attrs: &node.attrs,
             ident: node.ident.clone(),
             fields: Field::multiple_from_syn(&node.fields, span),
         }
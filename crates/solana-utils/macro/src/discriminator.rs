@@ -0,0 +1,183 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use sha2::{Digest, Sha256};
+use syn::spanned::Spanned;
+use syn::{Attribute, DeriveInput, Error, Expr, ExprLit, Generics, Ident, Lit, LitStr, Result};
+
+use crate::ast::Input;
+
+/// The length of a discriminator in bytes.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// The namespace Anchor uses for instructions, whose names are converted to snake_case.
+pub const INSTRUCTION_NAMESPACE: &str = "global";
+
+pub fn derive(input: &DeriveInput) -> TokenStream {
+    let (attrs, ident, generics) = match Input::from_syn(input) {
+        Input::Struct(input) => (input.attrs, input.ident, input.generics),
+        Input::Enum(input) => (input.attrs, input.ident, input.generics),
+        Input::Union(_) => {
+            return Error::new(Span::call_site(), "this trait cannot be derived for unions")
+                .to_compile_error();
+        }
+    };
+    impl_discriminator(attrs, &ident, generics).unwrap_or_else(Error::into_compile_error)
+}
+
+fn impl_discriminator(
+    attrs: &[Attribute],
+    ident: &Ident,
+    generics: &Generics,
+) -> Result<TokenStream> {
+    let mut namespace: Option<LitStr> = None;
+    let mut name: Option<LitStr> = None;
+    let mut bytes: Option<Expr> = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("discriminator")) {
+        attr.parse_nested_meta(|meta| {
+            let (slot, key) = if meta.path.is_ident("namespace") {
+                (&mut namespace, "namespace")
+            } else if meta.path.is_ident("name") {
+                (&mut name, "name")
+            } else if meta.path.is_ident("bytes") {
+                if bytes.is_some() {
+                    return Err(meta.error("`bytes` specified more than once"));
+                }
+                bytes = Some(meta.value()?.parse()?);
+                return Ok(());
+            } else {
+                return Err(
+                    meta.error("unsupported attribute, expected `namespace`, `name` or `bytes`")
+                );
+            };
+
+            if slot.is_some() {
+                return Err(meta.error(format!("`{key}` specified more than once")));
+            }
+            *slot = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+
+    let discriminator = match bytes {
+        Some(bytes) => {
+            if let Some(other) = namespace.as_ref().or(name.as_ref()) {
+                return Err(Error::new(
+                    other.span(),
+                    "`bytes` cannot be combined with `namespace` or `name`",
+                ));
+            }
+            override_bytes(bytes)?
+        }
+        None => {
+            let namespace = namespace.map_or_else(|| "account".to_owned(), |lit| lit.value());
+            let name = match name {
+                Some(name) => name.value(),
+                None if namespace == INSTRUCTION_NAMESPACE => to_snake_case(&ident.to_string()),
+                None => ident.to_string(),
+            };
+
+            let bytes = anchor_discriminator(&namespace, &name);
+            quote! { [#(#bytes),*] }
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::solana_utils::Discriminator for #ident #ty_generics #where_clause {
+            const DISCRIMINATOR: [u8; ::solana_utils::DISCRIMINATOR_LEN] = #discriminator;
+        }
+    })
+}
+
+fn override_bytes(bytes: Expr) -> Result<TokenStream> {
+    match &bytes {
+        Expr::Lit(ExprLit { lit: Lit::ByteStr(lit), .. }) => {
+            let len = lit.value().len();
+            if len != DISCRIMINATOR_LEN {
+                return Err(Error::new(
+                    lit.span(),
+                    format!("discriminators must be {DISCRIMINATOR_LEN} bytes long, got {len}"),
+                ));
+            }
+            Ok(quote! { *#lit })
+        }
+        Expr::Array(array) => {
+            if array.elems.len() != DISCRIMINATOR_LEN {
+                return Err(Error::new(
+                    array.span(),
+                    format!(
+                        "discriminators must be {DISCRIMINATOR_LEN} bytes long, got {}",
+                        array.elems.len()
+                    ),
+                ));
+            }
+            Ok(quote! { #array })
+        }
+        _ => Err(Error::new(bytes.span(), "expected a byte string literal or an array of bytes")),
+    }
+}
+
+/// Computes a discriminator as Anchor does, from the sha256 hash of `"<namespace>:<name>"`.
+pub fn anchor_discriminator(namespace: &str, name: &str) -> [u8; DISCRIMINATOR_LEN] {
+    let hash = Sha256::digest(format!("{namespace}:{name}"));
+    let mut discriminator = [0; DISCRIMINATOR_LEN];
+    discriminator.copy_from_slice(&hash[..DISCRIMINATOR_LEN]);
+    discriminator
+}
+
+/// Converts a name to snake_case as `heck::ToSnakeCase` does, which is what Anchor uses for
+/// instruction names.
+///
+/// Unlike the `snake_case` rename rule, a run of uppercase letters is kept as one word, so
+/// `SetURI` becomes `set_uri` and `HTTPServer` becomes `http_server`.
+pub fn to_snake_case(name: &str) -> String {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mode {
+        Boundary,
+        Lowercase,
+        Uppercase,
+    }
+
+    let mut words = Vec::new();
+
+    for word in name.split(|c: char| !c.is_alphanumeric()) {
+        let mut chars = word.char_indices().peekable();
+        let mut start = 0;
+        let mut mode = Mode::Boundary;
+
+        while let Some((i, c)) = chars.next() {
+            let Some(&(next_i, next)) = chars.peek() else {
+                words.push(&word[start..]);
+                break;
+            };
+
+            let next_mode = if c.is_lowercase() {
+                Mode::Lowercase
+            } else if c.is_uppercase() {
+                Mode::Uppercase
+            } else {
+                mode
+            };
+
+            if next_mode == Mode::Lowercase && next.is_uppercase() {
+                // A word ends before an uppercase letter that follows a lowercase one.
+                words.push(&word[start..next_i]);
+                start = next_i;
+                mode = Mode::Boundary;
+            } else if mode == Mode::Uppercase && c.is_uppercase() && next.is_lowercase() {
+                // A word starts at the last uppercase letter of a run followed by a lowercase one.
+                words.push(&word[start..i]);
+                start = i;
+                mode = Mode::Boundary;
+            } else {
+                mode = next_mode;
+            }
+        }
+    }
+
+    words.iter().map(|word| word.to_lowercase()).collect::<Vec<_>>().join("_")
+}
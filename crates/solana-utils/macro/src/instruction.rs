@@ -0,0 +1,352 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Attribute, DeriveInput, Error, Ident, Lit, LitByteStr, LitStr, Path, Result};
+
+use crate::ast::{Enum, Input, Variant};
+use crate::discriminator::{
+    anchor_discriminator, to_snake_case, DISCRIMINATOR_LEN, INSTRUCTION_NAMESPACE,
+};
+
+pub fn derive(input: &DeriveInput) -> TokenStream {
+    let msg = match Input::from_syn(input) {
+        Input::Enum(input) => return impl_enum(input).unwrap_or_else(Error::into_compile_error),
+        Input::Struct(_) => "this trait cannot be derived for structs",
+        Input::Union(_) => "this trait cannot be derived for unions",
+    };
+    Error::new(Span::call_site(), msg).to_compile_error()
+}
+
+/// How the variant of an instruction is encoded at the start of the instruction data.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    /// A 1-byte tag, which is the index of the variant unless specified.
+    U8,
+    /// An 8-byte discriminator computed as Anchor does from the name of the variant.
+    Anchor,
+    /// A byte string specified for each variant.
+    Custom,
+}
+
+enum Tag {
+    U8(u8),
+    Anchor([u8; DISCRIMINATOR_LEN]),
+    Custom(Vec<u8>),
+}
+
+impl Tag {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Tag::U8(tag) => std::slice::from_ref(tag),
+            Tag::Anchor(tag) => tag,
+            Tag::Custom(tag) => tag,
+        }
+    }
+}
+
+struct VariantAttrs {
+    tag: Option<Lit>,
+    name: Option<LitStr>,
+    handler: Option<Path>,
+}
+
+fn impl_enum(input: Enum) -> Result<TokenStream> {
+    let mut kind = None;
+
+    for attr in instruction_attrs(input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                if kind.is_some() {
+                    return Err(meta.error("`tag` specified more than once"));
+                }
+                let ident: Ident = meta.value()?.parse()?;
+                kind = Some(match ident.to_string().as_str() {
+                    "u8" => TagKind::U8,
+                    "anchor" => TagKind::Anchor,
+                    "custom" => TagKind::Custom,
+                    _ => {
+                        return Err(Error::new(
+                            ident.span(),
+                            "unknown tag kind, expected `u8`, `anchor` or `custom`",
+                        ));
+                    }
+                });
+                Ok(())
+            } else {
+                Err(meta.error("unsupported attribute, expected `tag`"))
+            }
+        })?;
+    }
+
+    let kind = kind.unwrap_or(TagKind::U8);
+
+    let mut next_tag = Some(0u8);
+    let mut tags: Vec<(Tag, Span)> = Vec::with_capacity(input.variants.len());
+    let mut handlers = Vec::with_capacity(input.variants.len());
+
+    for variant in &input.variants {
+        let attrs = variant_attrs(variant)?;
+
+        let (tag, span) = match (kind, &attrs.tag) {
+            (TagKind::U8, Some(Lit::Int(lit))) => (Tag::U8(lit.base10_parse()?), lit.span()),
+            (TagKind::U8, None) => match next_tag {
+                Some(tag) => (Tag::U8(tag), variant.ident.span()),
+                None => {
+                    return Err(Error::new(
+                        variant.ident.span(),
+                        "instruction tag overflows `u8`, specify it with `tag = ..`",
+                    ));
+                }
+            },
+            (TagKind::Anchor, Some(Lit::ByteStr(lit))) => {
+                let tag = lit.value().try_into().map_err(|tag: Vec<u8>| {
+                    Error::new(
+                        lit.span(),
+                        format!(
+                            "discriminators must be {DISCRIMINATOR_LEN} bytes long, got {}",
+                            tag.len()
+                        ),
+                    )
+                })?;
+                (Tag::Anchor(tag), lit.span())
+            }
+            (TagKind::Anchor, None) => {
+                let name = match &attrs.name {
+                    Some(name) => name.value(),
+                    None => to_snake_case(&variant.ident.to_string()),
+                };
+                (
+                    Tag::Anchor(anchor_discriminator(INSTRUCTION_NAMESPACE, &name)),
+                    variant.ident.span(),
+                )
+            }
+            (TagKind::Custom, Some(Lit::ByteStr(lit))) => (Tag::Custom(lit.value()), lit.span()),
+            (TagKind::Custom, None) => {
+                return Err(Error::new(
+                    variant.ident.span(),
+                    "custom instruction tags must be specified with `tag = b\"...\"`",
+                ));
+            }
+            (TagKind::U8, Some(lit)) => {
+                return Err(Error::new(lit.span(), "expected an integer literal"));
+            }
+            (_, Some(lit)) => {
+                return Err(Error::new(lit.span(), "expected a byte string literal"));
+            }
+        };
+
+        if let (Some(name), TagKind::U8 | TagKind::Custom) = (&attrs.name, kind) {
+            return Err(Error::new(name.span(), "`name` is only supported with `tag = anchor`"));
+        }
+        if let (Some(name), Some(_)) = (&attrs.name, &attrs.tag) {
+            return Err(Error::new(name.span(), "`name` cannot be combined with `tag`"));
+        }
+
+        for (other, other_span) in &tags {
+            let (a, b) = (other.bytes(), tag.bytes());
+            if a.starts_with(b) || b.starts_with(a) {
+                let msg = match a == b {
+                    true => "duplicate instruction tag",
+                    false => "instruction tag overlaps with the tag of another instruction",
+                };
+                let mut err = Error::new(span, msg);
+                err.combine(Error::new(*other_span, "other tag specified here"));
+                return Err(err);
+            }
+        }
+
+        if let Tag::U8(tag) = tag {
+            next_tag = tag.checked_add(1);
+        }
+        tags.push((tag, span));
+
+        handlers.push(match attrs.handler {
+            Some(handler) => quote! { #handler },
+            None => {
+                let ident = &variant.ident;
+                let handler = format_ident!(
+                    "process_{}",
+                    to_snake_case(&ident.to_string()),
+                    span = ident.span()
+                );
+                quote! { #handler }
+            }
+        });
+    }
+
+    let private = quote! { ::solana_utils::instruction::__private };
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let constructors: Vec<_> = input
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = &variant.ident;
+            let name = ident.to_string();
+            let fields = variant.fields.iter().map(|field| {
+                let member = &field.member;
+                let field_ty = field.ty;
+                quote_spanned! { field_ty.span() =>
+                    #member: #private::deserialize::<#field_ty>(&mut data, #name)?,
+                }
+            });
+            quote! { #ty::#ident { #(#fields)* } }
+        })
+        .collect();
+
+    let unpack = match kind {
+        TagKind::U8 => {
+            let tags = tags.iter().map(|(tag, _)| tag.bytes()[0]);
+            quote! {
+                let (tag, mut data) = match data.split_first() {
+                    ::core::option::Option::Some((tag, data)) => (*tag, data),
+                    ::core::option::Option::None => {
+                        return ::core::result::Result::Err(
+                            #private::unknown_instruction(data),
+                        );
+                    }
+                };
+                let instruction = match tag {
+                    #(#tags => #constructors,)*
+                    _ => {
+                        return ::core::result::Result::Err(
+                            #private::unknown_instruction(&[tag]),
+                        );
+                    }
+                };
+            }
+        }
+        TagKind::Anchor => {
+            let tags = tags.iter().map(|(tag, _)| {
+                let tag = u64::from_le_bytes(tag.bytes().try_into().unwrap());
+                quote! { #tag }
+            });
+            quote! {
+                if data.len() < #DISCRIMINATOR_LEN {
+                    return ::core::result::Result::Err(
+                        #private::unknown_instruction(data),
+                    );
+                }
+                let (tag, mut data) = data.split_at(#DISCRIMINATOR_LEN);
+                let mut discriminator = [0; #DISCRIMINATOR_LEN];
+                discriminator.copy_from_slice(tag);
+                let instruction = match u64::from_le_bytes(discriminator) {
+                    #(#tags => #constructors,)*
+                    _ => {
+                        return ::core::result::Result::Err(
+                            #private::unknown_instruction(tag),
+                        );
+                    }
+                };
+            }
+        }
+        TagKind::Custom => {
+            let branches = tags.iter().zip(&constructors).map(|(tag, constructor)| {
+                let tag = LitByteStr::new(tag.0.bytes(), tag.1);
+                quote! {
+                    if let ::core::option::Option::Some(rest) = data.strip_prefix(&#tag[..]) {
+                        data = rest;
+                        #constructor
+                    }
+                }
+            });
+            quote! {
+                let mut data = data;
+                let instruction = #(#branches else)* {
+                    return ::core::result::Result::Err(
+                        #private::unknown_instruction(data),
+                    );
+                };
+            }
+        }
+    };
+
+    let arms = input.variants.iter().zip(&handlers).map(|(variant, handler)| {
+        let ident = &variant.ident;
+        let members = variant.fields.iter().map(|field| &field.member);
+        let bindings: Vec<_> =
+            (0..variant.fields.len()).map(|i| format_ident!("__arg{}", i)).collect();
+        quote! {
+            #ty::#ident { #(#members: #bindings,)* } => {
+                #handler(program_id, accounts, #(#bindings),*)
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::solana_utils::instruction::InstructionDispatch
+            for #ty #ty_generics #where_clause
+        {
+            #[allow(unused_mut)]
+            fn unpack(data: &[u8]) -> ::core::result::Result<Self, #private::ProgramError> {
+                #unpack
+
+                if !data.is_empty() {
+                    return ::core::result::Result::Err(
+                        #private::trailing_data(
+                            ::solana_utils::VariantName::variant_name(&instruction),
+                            data,
+                        ),
+                    );
+                }
+
+                ::core::result::Result::Ok(instruction)
+            }
+
+            fn dispatch(
+                self,
+                program_id: &#private::Pubkey,
+                accounts: &[#private::AccountInfo],
+            ) -> #private::ProgramResult {
+                ::solana_utils::log_debug!(
+                    "Instruction: {}",
+                    ::solana_utils::VariantName::variant_name(&self),
+                );
+
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+fn variant_attrs(variant: &Variant) -> Result<VariantAttrs> {
+    let mut attrs = VariantAttrs { tag: None, name: None, handler: None };
+
+    for attr in instruction_attrs(variant.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                if attrs.tag.is_some() {
+                    return Err(meta.error("`tag` specified more than once"));
+                }
+                attrs.tag = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("name") {
+                if attrs.name.is_some() {
+                    return Err(meta.error("`name` specified more than once"));
+                }
+                attrs.name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("handler") {
+                if attrs.handler.is_some() {
+                    return Err(meta.error("`handler` specified more than once"));
+                }
+                attrs.handler = Some(meta.value()?.parse()?);
+            } else {
+                return Err(
+                    meta.error("unsupported attribute, expected `tag`, `name` or `handler`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(attrs)
+}
+
+fn instruction_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("instruction"))
+}
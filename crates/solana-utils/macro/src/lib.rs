@@ -3,11 +3,54 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
 
+mod accounts;
 mod ast;
+mod discriminator;
+mod instruction;
+mod log;
+mod pda;
+mod program_error;
 mod variant_name;
 
-#[proc_macro_derive(VariantName)]
+#[proc_macro_derive(Accounts, attributes(account))]
+pub fn derive_accounts(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    accounts::derive(&input).into()
+}
+
+#[proc_macro_derive(VariantName, attributes(variant_name))]
 pub fn derive_variant_name(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     variant_name::derive(&input).into()
 }
+
+#[proc_macro_derive(Discriminator, attributes(discriminator))]
+pub fn derive_discriminator(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    discriminator::derive(&input).into()
+}
+
+#[proc_macro_derive(InstructionDispatch, attributes(instruction))]
+pub fn derive_instruction_dispatch(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    instruction::derive(&input).into()
+}
+
+#[proc_macro_derive(Pda, attributes(pda))]
+pub fn derive_pda(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    pda::derive(&input).into()
+}
+
+#[proc_macro_derive(ProgramError, attributes(error_code))]
+pub fn derive_program_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    program_error::derive(&input).into()
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn format_log(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as log::Input);
+    log::expand(input).into()
+}
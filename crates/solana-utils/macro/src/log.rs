@@ -0,0 +1,385 @@
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::{Error, Expr, LitStr, Result, Token};
+
+/// The input of `format_log!`: the path of the `solana_utils` crate, followed by the arguments of
+/// `log!`.
+pub struct Input {
+    krate: TokenTree,
+    format: Format,
+    args: Vec<Arg>,
+}
+
+/// The format string, which is either a literal or a macro that expands to one, such as
+/// `concat!`.
+enum Format {
+    Lit(LitStr),
+    Macro(Expr),
+}
+
+impl ToTokens for Format {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Format::Lit(lit) => lit.to_tokens(tokens),
+            Format::Macro(expr) => expr.to_tokens(tokens),
+        }
+    }
+}
+
+struct Arg {
+    name: Option<Ident>,
+    expr: Expr,
+    used: bool,
+}
+
+enum Piece {
+    Literal(String),
+    Placeholder { arg: usize, spec: Option<String> },
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let krate = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        let format = if input.peek(LitStr) {
+            Format::Lit(input.parse()?)
+        } else {
+            Format::Macro(input.parse()?)
+        };
+        let mut args = Vec::new();
+
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let name =
+                if input.peek(syn::Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+                    let name = input.parse()?;
+                    input.parse::<Token![=]>()?;
+                    Some(name)
+                } else {
+                    None
+                };
+
+            args.push(Arg { name, expr: input.parse()?, used: false });
+        }
+
+        Ok(Input { krate, format, args })
+    }
+}
+
+pub fn expand(mut input: Input) -> TokenStream {
+    let krate = input.krate.clone();
+
+    let format = match &input.format {
+        Format::Lit(format) => format.clone(),
+        // The format string is only known once the macro is expanded, so format the whole
+        // message with `core::fmt`.
+        Format::Macro(_) => return fallback(&input),
+    };
+
+    let pieces = match parse_format(&mut input, &format) {
+        Ok(Some(pieces)) => pieces,
+        // The format string uses features that are not specialized, such as arguments for the
+        // width or precision, so format the whole message with `core::fmt`.
+        Ok(None) => return fallback(&input),
+        Err(err) => return err.into_compile_error(),
+    };
+
+    if let Some(arg) = input.args.iter().find(|arg| !arg.used) {
+        let msg = match arg.name {
+            Some(_) => "named argument never used",
+            None => "argument never used",
+        };
+        return Error::new_spanned(&arg.expr, msg).into_compile_error();
+    }
+
+    let bindings: Vec<_> = (0..input.args.len()).map(|i| format_ident!("__arg{}", i)).collect();
+    let exprs = input.args.iter().map(|arg| &arg.expr);
+
+    let body = match pieces.as_slice() {
+        [] => return quote! { #krate::log::__private::sol_log("") },
+        [Piece::Literal(msg)] => return quote! { #krate::log::__private::sol_log(#msg) },
+        [Piece::Placeholder { arg, spec: None }] => {
+            let binding = &bindings[*arg];
+            quote! {
+                #[allow(unused_imports)]
+                use #krate::log::__private::{ViaDisplay as _, ViaLoggable as _};
+
+                (&#krate::log::__private::Wrap(#binding)).__log();
+            }
+        }
+        pieces => {
+            let writes = pieces.iter().map(|piece| match piece {
+                Piece::Literal(literal) => quote! {
+                    __buf.push_str(#literal);
+                },
+                Piece::Placeholder { arg, spec: None } => {
+                    let binding = &bindings[*arg];
+                    quote! {
+                        (&#krate::log::__private::Wrap(#binding)).__write_log(__buf);
+                    }
+                }
+                Piece::Placeholder { arg, spec: Some(spec) } => {
+                    let binding = &bindings[*arg];
+                    let format = LitStr::new(&format!("{{:{spec}}}"), format.span());
+                    quote! {
+                        let _ = ::core::fmt::Write::write_fmt(
+                            __buf,
+                            ::core::format_args!(#format, #binding),
+                        );
+                    }
+                }
+            });
+
+            quote! {
+                #[allow(unused_imports)]
+                use #krate::log::__private::{ViaDisplay as _, ViaLoggable as _};
+
+                #krate::log::__private::log_with(|__buf| {
+                    #(#writes)*
+                });
+            }
+        }
+    };
+
+    quote! {
+        match (#(&(#exprs),)*) {
+            (#(#bindings,)*) => {
+                #body
+            }
+        }
+    }
+}
+
+fn fallback(input: &Input) -> TokenStream {
+    let krate = &input.krate;
+    let format = &input.format;
+    let args = input.args.iter().map(|arg| {
+        let expr = &arg.expr;
+        match &arg.name {
+            Some(name) => quote! { #name = #expr },
+            None => quote! { #expr },
+        }
+    });
+
+    quote! {
+        #krate::log::__private::log_with(|__buf| {
+            let _ = ::core::fmt::Write::write_fmt(__buf, ::core::format_args!(#format, #(#args),*));
+        })
+    }
+}
+
+/// Parses the format string into pieces, resolving the argument of each placeholder.
+///
+/// Returns `None` if the format string cannot be specialized.
+fn parse_format(input: &mut Input, format: &LitStr) -> Result<Option<Vec<Piece>>> {
+    let span = format.span();
+    let format = format.value();
+
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut next_arg = 0;
+
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '}' => return Err(Error::new(span, "invalid format string: unmatched `}` found")),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => {
+                            return Err(Error::new(
+                                span,
+                                "invalid format string: expected `}` but string was terminated",
+                            ));
+                        }
+                    }
+                }
+
+                let (arg, spec) = match placeholder.split_once(':') {
+                    Some((arg, spec)) => (arg.trim(), Some(spec)),
+                    None => (placeholder.trim(), None),
+                };
+
+                if spec.is_some_and(|spec| spec.contains('$') || spec.contains('*')) {
+                    return Ok(None);
+                }
+
+                let arg = if arg.is_empty() {
+                    next_arg += 1;
+                    positional(input, next_arg - 1, span)?
+                } else if let Ok(index) = arg.parse::<usize>() {
+                    positional(input, index, span)?
+                } else {
+                    named(input, arg, span)?
+                };
+
+                input.args[arg].used = true;
+
+                if !literal.is_empty() {
+                    pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                }
+                let spec = spec.filter(|spec| !spec.is_empty()).map(str::to_owned);
+                pieces.push(Piece::Placeholder { arg, spec });
+            }
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+
+    Ok(Some(pieces))
+}
+
+fn positional(input: &Input, index: usize, span: Span) -> Result<usize> {
+    let count = input.args.iter().filter(|arg| arg.name.is_none()).count();
+
+    if index >= count {
+        let msg = match count {
+            1 => format!("invalid reference to positional argument {index} (there is 1 argument)"),
+            _ => format!(
+                "invalid reference to positional argument {index} (there are {count} arguments)"
+            ),
+        };
+        return Err(Error::new(span, msg));
+    }
+
+    Ok(index)
+}
+
+fn named(input: &mut Input, name: &str, span: Span) -> Result<usize> {
+    if let Some(index) =
+        input.args.iter().position(|arg| arg.name.as_ref().is_some_and(|n| n == name))
+    {
+        return Ok(index);
+    }
+
+    let ident = match syn::parse_str::<Ident>(name) {
+        Ok(ident) => Ident::new(&ident.to_string(), span),
+        Err(_) => return Err(Error::new(span, format!("invalid format string argument `{name}`"))),
+    };
+
+    // Implicitly capture the variable, as `format_args!` does.
+    input.args.push(Arg {
+        name: Some(ident.clone()),
+        expr: syn::parse_quote!(#ident),
+        used: false,
+    });
+
+    Ok(input.args.len() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the arguments of `log!` and their format string.
+    fn parse_input(args: &str) -> (Input, Result<Option<Vec<Piece>>>) {
+        let mut input: Input = syn::parse_str(&format!("krate, {args}")).unwrap();
+        let Format::Lit(format) = &input.format else { panic!("not a literal") };
+        let format = format.clone();
+
+        let pieces = parse_format(&mut input, &format);
+        (input, pieces)
+    }
+
+    /// Parses the format string, rendering literal pieces as is and placeholders with the index of
+    /// their argument.
+    fn parse(args: &str) -> std::result::Result<Option<Vec<String>>, String> {
+        let pieces = parse_input(args).1.map_err(|err| err.to_string())?;
+        Ok(pieces.map(|pieces| {
+            pieces
+                .into_iter()
+                .map(|piece| match piece {
+                    Piece::Literal(literal) => literal,
+                    Piece::Placeholder { arg, spec: None } => format!("{{{arg}}}"),
+                    Piece::Placeholder { arg, spec: Some(spec) } => format!("{{{arg}:{spec}}}"),
+                })
+                .collect()
+        }))
+    }
+
+    fn expand_str(args: &str) -> String {
+        expand(syn::parse_str(&format!("krate, {args}")).unwrap()).to_string()
+    }
+
+    #[test]
+    fn placeholders() {
+        let pieces = parse(r#""a {} b {0} c {name} {}", 1, 2, name = 3"#).unwrap().unwrap();
+        assert_eq!(pieces, ["a ", "{0}", " b ", "{0}", " c ", "{2}", " ", "{1}"]);
+
+        let pieces = parse(r#""{:?}{0:>4}{:}", 1, 2"#).unwrap().unwrap();
+        assert_eq!(pieces, ["{0:?}", "{0:>4}", "{1}"]);
+
+        assert_eq!(parse(r#""""#).unwrap().unwrap(), Vec::<String>::new());
+        assert_eq!(parse(r#""{:1$}", 1, 2"#), Ok(None));
+        assert_eq!(parse(r#""{:.*}", 1, 2"#), Ok(None));
+    }
+
+    #[test]
+    fn implicit_captures() {
+        let (input, pieces) = parse_input(r#""{x} {y} {x}""#);
+        let pieces = pieces.unwrap().unwrap();
+
+        assert_eq!(pieces.len(), 5);
+        let names: Vec<_> = input.args.iter().map(|arg| arg.name.as_ref().unwrap()).collect();
+        assert_eq!(names, ["x", "y"]);
+    }
+
+    #[test]
+    fn escapes() {
+        let pieces = parse(r#""{{}} {{{}}}", 1"#).unwrap().unwrap();
+        assert_eq!(pieces, ["{} {", "{0}", "}"]);
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            (r#""}""#, "invalid format string: unmatched `}` found"),
+            (r#""{} }", 1"#, "invalid format string: unmatched `}` found"),
+            (r#""{""#, "invalid format string: expected `}` but string was terminated"),
+            (r#""{0""#, "invalid format string: expected `}` but string was terminated"),
+            (r#""{}""#, "invalid reference to positional argument 0 (there are 0 arguments)"),
+            (r#""{} {}", 1"#, "invalid reference to positional argument 1 (there is 1 argument)"),
+            (
+                r#""{2}", 1, 2"#,
+                "invalid reference to positional argument 2 (there are 2 arguments)",
+            ),
+            (
+                r#""{0}", name = 1"#,
+                "invalid reference to positional argument 0 (there are 0 arguments)",
+            ),
+            (r#""{1x}""#, "invalid format string argument `1x`"),
+        ];
+
+        for (args, msg) in cases {
+            assert_eq!(parse(args), Err(msg.to_owned()), "{args}");
+        }
+    }
+
+    #[test]
+    fn unused_arguments() {
+        assert!(expand_str(r#""{}", 1, 2"#).contains("\"argument never used\""));
+        assert!(expand_str(r#""{}", 1, name = 2"#).contains("\"named argument never used\""));
+        assert!(!expand_str(r#""{} {name}", 1, name = 2"#).contains("compile_error"));
+    }
+}
@@ -0,0 +1,217 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{Attribute, DeriveInput, Error, Expr, Lit, LitByteStr, Result, Type};
+
+use crate::ast::{Field, Input, Struct};
+
+/// The maximum number of seeds, excluding the bump seed.
+const MAX_SEEDS: usize = 15;
+/// The maximum length of a seed.
+const MAX_SEED_LEN: usize = 32;
+
+const INTEGERS: &[&str] = &["u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128"];
+
+pub fn derive(input: &DeriveInput) -> TokenStream {
+    let msg = match Input::from_syn(input) {
+        Input::Struct(input) => {
+            return impl_struct(input).unwrap_or_else(Error::into_compile_error);
+        }
+        Input::Enum(_) => "this trait cannot be derived for enums",
+        Input::Union(_) => "this trait cannot be derived for unions",
+    };
+    Error::new(Span::call_site(), msg).to_compile_error()
+}
+
+enum Seed<'a> {
+    Const(LitByteStr),
+    Pubkey(&'a Field<'a>),
+    Bytes(&'a Field<'a>, &'a Expr),
+    Int(&'a Field<'a>, Endian),
+}
+
+#[derive(Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+fn impl_struct(input: Struct) -> Result<TokenStream> {
+    let mut seeds = Vec::new();
+
+    for attr in pda_attrs(input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("prefix") {
+                seeds.push(Seed::Const(parse_prefix(meta.value()?.parse()?)?));
+                Ok(())
+            } else {
+                Err(meta.error("unsupported attribute, expected `prefix`"))
+            }
+        })?;
+    }
+
+    for field in &input.fields {
+        let mut endian = None;
+
+        for attr in pda_attrs(&field.original.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("prefix") {
+                    seeds.push(Seed::Const(parse_prefix(meta.value()?.parse()?)?));
+                } else if meta.path.is_ident("le") || meta.path.is_ident("be") {
+                    if endian.is_some() {
+                        return Err(meta.error("endianness specified more than once"));
+                    }
+                    let value = if meta.path.is_ident("le") { Endian::Little } else { Endian::Big };
+                    endian = Some((value, meta.path.span()));
+                } else {
+                    return Err(
+                        meta.error("unsupported attribute, expected `prefix`, `le` or `be`")
+                    );
+                }
+                Ok(())
+            })?;
+        }
+
+        seeds.push(field_seed(field, endian)?);
+    }
+
+    if seeds.len() > MAX_SEEDS {
+        return Err(Error::new(
+            Span::call_site(),
+            format!("too many seeds, at most {MAX_SEEDS} are supported alongside the bump seed"),
+        ));
+    }
+
+    let mut assertions = Vec::new();
+
+    let pushes: Vec<_> = seeds
+        .iter()
+        .map(|seed| match seed {
+            Seed::Const(bytes) => quote! {
+                seeds.push(#bytes);
+            },
+            Seed::Pubkey(field) => {
+                let member = &field.member;
+                quote_spanned! { field.ty.span() =>
+                    seeds.push(::core::convert::AsRef::<[u8]>::as_ref(&self.#member));
+                }
+            }
+            Seed::Bytes(field, len) => {
+                let member = &field.member;
+                let msg = format!("seeds cannot be longer than {MAX_SEED_LEN} bytes");
+
+                assertions.push(quote_spanned! { len.span() =>
+                    ::core::assert!((#len) <= #MAX_SEED_LEN, #msg);
+                });
+
+                quote! {
+                    seeds.push(&self.#member);
+                }
+            }
+            Seed::Int(field, endian) => {
+                let member = &field.member;
+                let ty = field.ty;
+                let to_bytes = match endian {
+                    Endian::Little => quote! { to_le_bytes },
+                    Endian::Big => quote! { to_be_bytes },
+                };
+                quote_spanned! { ty.span() =>
+                    seeds.push(&<#ty>::#to_bytes(self.#member));
+                }
+            }
+        })
+        .collect();
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // The seed lengths are asserted in an associated const, rather than a free item, so they can
+    // depend on the generic parameters of the type. The const is evaluated when referenced from
+    // `write_seeds`.
+    let (check_impl, check) = if assertions.is_empty() {
+        (None, None)
+    } else {
+        let check_impl = quote! {
+            #[automatically_derived]
+            impl #impl_generics #ty #ty_generics #where_clause {
+                #[doc(hidden)]
+                const __PDA_SEED_LEN_CHECK: () = {
+                    #(#assertions)*
+                };
+            }
+        };
+        (Some(check_impl), Some(quote! { let () = Self::__PDA_SEED_LEN_CHECK; }))
+    };
+
+    Ok(quote! {
+        #check_impl
+
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::solana_utils::pda::Pda for #ty #ty_generics #where_clause {
+            fn write_seeds(&self, seeds: &mut ::solana_utils::pda::OwnedSeeds) {
+                #check
+                #(#pushes)*
+            }
+        }
+    })
+}
+
+fn pda_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("pda"))
+}
+
+fn parse_prefix(lit: Lit) -> Result<LitByteStr> {
+    let bytes = match &lit {
+        Lit::ByteStr(lit) => lit.value(),
+        Lit::Str(lit) => lit.value().into_bytes(),
+        _ => return Err(Error::new(lit.span(), "expected a byte string or string literal")),
+    };
+
+    if bytes.len() > MAX_SEED_LEN {
+        return Err(Error::new(
+            lit.span(),
+            format!("seeds cannot be longer than {MAX_SEED_LEN} bytes, got {}", bytes.len()),
+        ));
+    }
+
+    Ok(LitByteStr::new(&bytes, lit.span()))
+}
+
+fn field_seed<'a>(field: &'a Field<'a>, endian: Option<(Endian, Span)>) -> Result<Seed<'a>> {
+    let seed = match field.ty {
+        Type::Path(ty) if ty.qself.is_none() => {
+            let ident = ty.path.segments.last().map(|segment| &segment.ident);
+
+            match ident {
+                Some(ident) if ident == "Pubkey" => Some(Seed::Pubkey(field)),
+                Some(ident) if INTEGERS.iter().any(|int| ident == int) => {
+                    let endian = endian.map_or(Endian::Little, |(endian, _)| endian);
+                    return Ok(Seed::Int(field, endian));
+                }
+                _ => None,
+            }
+        }
+        Type::Array(ty) => match &*ty.elem {
+            Type::Path(elem) if elem.path.is_ident("u8") => Some(Seed::Bytes(field, &ty.len)),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let seed = match seed {
+        Some(seed) => seed,
+        None => {
+            return Err(Error::new(
+                field.ty.span(),
+                "unsupported seed type, expected `Pubkey`, a primitive integer or `[u8; N]`",
+            ));
+        }
+    };
+
+    if let Some((_, span)) = endian {
+        return Err(Error::new(span, "endianness can only be specified for integer seeds"));
+    }
+
+    Ok(seed)
+}
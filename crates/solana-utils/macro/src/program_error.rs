@@ -0,0 +1,124 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Attribute, DeriveInput, Error, Expr, ExprLit, Lit, LitInt, Result};
+
+use crate::ast::{Enum, Input};
+
+pub fn derive(input: &DeriveInput) -> TokenStream {
+    let msg = match Input::from_syn(input) {
+        Input::Enum(input) => return impl_enum(input).unwrap_or_else(Error::into_compile_error),
+        Input::Struct(_) => "this trait cannot be derived for structs",
+        Input::Union(_) => "this trait cannot be derived for unions",
+    };
+    Error::new(Span::call_site(), msg).to_compile_error()
+}
+
+fn impl_enum(input: Enum) -> Result<TokenStream> {
+    let mut next_code = Some(0);
+
+    for attr in error_code_attrs(input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("offset") {
+                next_code = Some(parse_code(&meta.value()?.parse()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported attribute, expected `offset`"))
+            }
+        })?;
+    }
+
+    let mut codes: Vec<(u32, Span)> = Vec::with_capacity(input.variants.len());
+
+    for variant in &input.variants {
+        if !variant.fields.is_empty() {
+            return Err(Error::new(
+                variant.original.fields.span(),
+                "error variants cannot have fields",
+            ));
+        }
+
+        let explicit = match error_code_attrs(variant.attrs).last() {
+            Some(attr) => {
+                let lit: LitInt = attr.parse_args()?;
+                Some((parse_code(&lit)?, lit.span()))
+            }
+            None => match &variant.original.discriminant {
+                Some((_, Expr::Lit(ExprLit { lit: Lit::Int(lit), .. }))) => {
+                    Some((parse_code(lit)?, lit.span()))
+                }
+                _ => None,
+            },
+        };
+
+        let (code, span) = match explicit {
+            Some(explicit) => explicit,
+            None => match next_code {
+                Some(code) => (code, variant.ident.span()),
+                None => {
+                    return Err(Error::new(
+                        variant.ident.span(),
+                        "error code overflows `u32`, specify it with `#[error_code(..)]`",
+                    ));
+                }
+            },
+        };
+
+        if let Some((_, other)) = codes.iter().find(|(other, _)| *other == code) {
+            let mut err = Error::new(span, format!("duplicate error code {code}"));
+            err.combine(Error::new(*other, "first used here"));
+            return Err(err);
+        }
+
+        codes.push((code, span));
+        next_code = code.checked_add(1);
+    }
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let idents: Vec<_> = input.variants.iter().map(|variant| &variant.ident).collect();
+    let codes: Vec<_> = codes.iter().map(|(code, _)| code).collect();
+
+    Ok(quote! {
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::core::convert::From<#ty #ty_generics>
+            for ::solana_utils::__private::ProgramError #where_clause
+        {
+            #[cold]
+            fn from(error: #ty #ty_generics) -> Self {
+                let code: u32 = match error {
+                    #(#ty::#idents => #codes,)*
+                };
+                ::solana_utils::log!(
+                    "Error: {} ({})",
+                    ::solana_utils::VariantName::variant_name(&error),
+                    code,
+                );
+                ::solana_utils::__private::ProgramError::Custom(code)
+            }
+        }
+
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::core::convert::TryFrom<u32> for #ty #ty_generics #where_clause {
+            type Error = u32;
+
+            fn try_from(code: u32) -> ::core::result::Result<Self, u32> {
+                match code {
+                    #(#codes => ::core::result::Result::Ok(#ty::#idents),)*
+                    code => ::core::result::Result::Err(code),
+                }
+            }
+        }
+    })
+}
+
+fn error_code_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("error_code"))
+}
+
+fn parse_code(lit: &LitInt) -> Result<u32> {
+    lit.base10_parse()
+}
@@ -1,38 +1,195 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{DeriveInput, Error};
+use syn::{Attribute, DeriveInput, Error, LitStr, Result};
 
 use crate::ast::{Enum, Input};
 
 pub fn derive(input: &DeriveInput) -> TokenStream {
     let msg = match Input::from_syn(input) {
-        Input::Enum(input) => return impl_enum(input),
+        Input::Enum(input) => return impl_enum(input).unwrap_or_else(Error::into_compile_error),
         Input::Struct(_) => "this trait cannot be derived for structs",
         Input::Union(_) => "this trait cannot be derived for unions",
     };
     Error::new(Span::call_site(), msg).to_compile_error()
 }
 
-fn impl_enum(input: Enum) -> TokenStream {
+/// A case convention applied to variant names with `#[variant_name(rename_all = "...")]`.
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+const RENAME_RULES: &[(&str, RenameRule)] = &[
+    ("lowercase", RenameRule::Lower),
+    ("UPPERCASE", RenameRule::Upper),
+    ("PascalCase", RenameRule::Pascal),
+    ("camelCase", RenameRule::Camel),
+    ("snake_case", RenameRule::Snake),
+    ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnake),
+    ("kebab-case", RenameRule::Kebab),
+    ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebab),
+];
+
+impl RenameRule {
+    fn from_lit(lit: &LitStr) -> Result<Self> {
+        let value = lit.value();
+
+        match RENAME_RULES.iter().find(|(name, _)| *name == value) {
+            Some((_, rule)) => Ok(*rule),
+            None => {
+                let names: Vec<_> = RENAME_RULES.iter().map(|(name, _)| *name).collect();
+                Err(Error::new(
+                    lit.span(),
+                    format!("unknown rename rule, expected one of {}", names.join(", ")),
+                ))
+            }
+        }
+    }
+
+    /// Applies the rule to a variant name, which is expected to be in PascalCase.
+    fn apply(self, variant: &str) -> String {
+        match self {
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Pascal => variant.to_owned(),
+            RenameRule::Camel => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::Snake => {
+                let mut snake = String::with_capacity(variant.len() + 4);
+                for (i, c) in variant.char_indices() {
+                    if i > 0 && c.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(c.to_ascii_lowercase());
+                }
+                snake
+            }
+            RenameRule::ScreamingSnake => RenameRule::Snake.apply(variant).to_ascii_uppercase(),
+            RenameRule::Kebab => RenameRule::Snake.apply(variant).replace('_', "-"),
+            RenameRule::ScreamingKebab => {
+                RenameRule::ScreamingSnake.apply(variant).replace('_', "-")
+            }
+        }
+    }
+}
+
+fn impl_enum(input: Enum) -> Result<TokenStream> {
+    let mut rename_all = None;
+
+    for attr in variant_name_attrs(input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                if rename_all.is_some() {
+                    return Err(meta.error("`rename_all` specified more than once"));
+                }
+                rename_all = Some(RenameRule::from_lit(&meta.value()?.parse()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported attribute, expected `rename_all`"))
+            }
+        })?;
+    }
+
+    let mut names: Vec<(String, Span)> = Vec::with_capacity(input.variants.len());
+
+    for variant in &input.variants {
+        let mut rename = None;
+
+        for attr in variant_name_attrs(variant.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if rename.is_some() {
+                        return Err(meta.error("`rename` specified more than once"));
+                    }
+                    let lit: LitStr = meta.value()?.parse()?;
+                    rename = Some((lit.value(), lit.span()));
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported attribute, expected `rename`"))
+                }
+            })?;
+        }
+
+        let (name, span) = match rename {
+            Some(rename) => rename,
+            None => {
+                let name = variant.ident.to_string();
+                let name = match rename_all {
+                    Some(rule) => rule.apply(&name),
+                    None => name,
+                };
+                (name, variant.ident.span())
+            }
+        };
+
+        if let Some((_, other)) = names.iter().find(|(other, _)| *other == name) {
+            let mut err = Error::new(span, format!("duplicate variant name `{name}`"));
+            err.combine(Error::new(*other, "first used here"));
+            return Err(err);
+        }
+
+        names.push((name, span));
+    }
+
     let ty = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    let variants = input.variants.iter().map(|variant| {
-        let variant = &variant.ident;
-        let name = variant.to_string();
+    let idents: Vec<_> = input.variants.iter().map(|variant| &variant.ident).collect();
+    let names: Vec<_> = names.iter().map(|(name, _)| name).collect();
+    let indices = 0..idents.len();
 
-        quote! { #ty::#variant { .. } => { #name } }
-    });
+    let constructors =
+        input.variants.iter().zip(&names).filter(|(variant, _)| variant.fields.is_empty()).map(
+            |(variant, name)| {
+                let variant = &variant.ident;
+                quote! { #name => ::core::option::Option::Some(#ty::#variant {}), }
+            },
+        );
 
-    quote! {
+    Ok(quote! {
         #[allow(unused_qualifications)]
         #[automatically_derived]
         impl #impl_generics ::solana_utils::VariantName for #ty #ty_generics #where_clause {
             fn variant_name(&self) -> &'static str {
-                match self {
-                    #(#variants)*
+                match *self {
+                    #(#ty::#idents { .. } => #names,)*
                 }
             }
         }
-    }
+
+        #[allow(unused_qualifications)]
+        #[automatically_derived]
+        impl #impl_generics ::solana_utils::VariantNames for #ty #ty_generics #where_clause {
+            const VARIANT_NAMES: &'static [&'static str] = &[#(#names),*];
+
+            fn variant_index(&self) -> usize {
+                match *self {
+                    #(#ty::#idents { .. } => #indices,)*
+                }
+            }
+
+            fn from_variant_name(name: &str) -> ::core::option::Option<Self> {
+                match name {
+                    #(#constructors)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+fn variant_name_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("variant_name"))
 }
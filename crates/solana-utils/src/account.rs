@@ -76,7 +76,7 @@ pub fn create_or_allocate_account<'a>(
 /// [`AccountInfo`] fields. It should only be called for instances of `AccountInfo` that were
 /// created by the runtime and received in the `process_instruction` entrypoint of a program.
 pub fn close_account<'a>(account: &AccountInfo<'a>, sol_dst: &AccountInfo<'a>) -> ProgramResult {
-    assert_is_solana!("close_account");
+    assert_is_solana!("close_account", account);
 
     let mut src_lamports = account.try_borrow_mut_lamports()?;
     let mut dst_lamports = sol_dst.try_borrow_mut_lamports()?;
@@ -96,6 +96,84 @@ pub fn close_account<'a>(account: &AccountInfo<'a>, sol_dst: &AccountInfo<'a>) -
     Ok(())
 }
 
+/// Discriminator written to the start of an account's data by [`close_account_secure`] when
+/// closing with [`CloseMode::Marker`].
+pub const CLOSED_ACCOUNT_DISCRIMINATOR: [u8; 8] = [u8::MAX; 8];
+
+/// How [`close_account_secure`] leaves the data of a closed account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CloseMode {
+    /// Zero the data, then reassign the account to the system program and reallocate it to zero
+    /// length, as [`close_account`] does.
+    Realloc,
+    /// Zero the data, then write [`CLOSED_ACCOUNT_DISCRIMINATOR`] to the start of it, leaving the
+    /// owner and length unchanged.
+    ///
+    /// This is for programs that cannot reallocate account data. Such programs should reject
+    /// closed accounts when loading them with [`check_not_closed`].
+    Marker,
+}
+
+/// Close `account` and transfer lamports to `sol_dst`, preventing the account from being revived
+/// with stale data.
+///
+/// Unlike [`close_account`], this rejects closing an account into itself and zeroes the account
+/// data before closing it.
+///
+/// # Safety
+///
+/// When closing with [`CloseMode::Realloc`], this function makes assumptions about the layout and
+/// location of memory referenced by [`AccountInfo`] fields. It should only be called for
+/// instances of `AccountInfo` that were created by the runtime and received in the
+/// `process_instruction` entrypoint of a program.
+pub fn close_account_secure<'a>(
+    account: &AccountInfo<'a>,
+    sol_dst: &AccountInfo<'a>,
+    mode: CloseMode,
+) -> ProgramResult {
+    if crate::pubkeys_eq(account.key, sol_dst.key) {
+        log!("Error: Cannot close account {} into itself", account.key);
+
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    {
+        let mut data = account.try_borrow_mut_data()?;
+
+        if mode == CloseMode::Marker && data.len() < CLOSED_ACCOUNT_DISCRIMINATOR.len() {
+            log!("Error: Account {} is too small to hold the closed marker", account.key);
+
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        crate::fill_fast(&mut data, 0);
+
+        if mode == CloseMode::Marker {
+            data[..CLOSED_ACCOUNT_DISCRIMINATOR.len()]
+                .copy_from_slice(&CLOSED_ACCOUNT_DISCRIMINATOR);
+        }
+    }
+
+    match mode {
+        CloseMode::Realloc => close_account(account, sol_dst),
+        CloseMode::Marker => transfer_lamports(account, sol_dst, account.lamports()),
+    }
+}
+
+/// Check that `account` has not been closed by [`close_account_secure`] with
+/// [`CloseMode::Marker`].
+pub fn check_not_closed(account: &AccountInfo) -> ProgramResult {
+    let data = account.try_borrow_data()?;
+
+    if data.starts_with(&CLOSED_ACCOUNT_DISCRIMINATOR) {
+        log!("Error: Account {} has been closed", account.key);
+
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
 /// Reallocate an account's data.
 ///
 /// Account data can be increased within a single call by up to [`MAX_PERMITTED_DATA_INCREASE`]
@@ -115,7 +193,7 @@ pub fn realloc_account_mut<'a>(
     info: &'a AccountInfo,
     new_len: usize,
 ) -> Result<RefMut<'a, [u8]>, ProgramError> {
-    assert_is_solana!("realloc_account");
+    assert_is_solana!("realloc_account", info);
 
     let mut data = info.try_borrow_mut_data()?;
 
@@ -147,6 +225,80 @@ pub fn realloc_account_mut<'a>(
     Ok(RefMut::map(data, |data| &mut data[..new_len]))
 }
 
+/// How [`resize_account`] treats bytes exposed by growing an account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ZeroPolicy {
+    /// Leave newly exposed bytes untouched.
+    ///
+    /// Memory used to grow is zero-initialized upon program entrypoint, so this is only unsafe if
+    /// the account was shrunk earlier within the same call.
+    Skip,
+    /// Zero newly exposed bytes.
+    Zero,
+}
+
+/// Resize an account's data, keeping it rent-exempt.
+///
+/// When growing, the lamports needed for the account to remain rent-exempt are transferred from
+/// `payer`: directly if `payer` is owned by the same program as `account`, or otherwise with a
+/// system program transfer, in which case `payer` must sign. When shrinking, the rent-exempt
+/// balance no longer required is refunded to `payer`, leaving any other lamports in place.
+///
+/// Account data can be increased within a single call by up to [`MAX_PERMITTED_DATA_INCREASE`]
+/// bytes in total from its original length, across any number of resizes.
+///
+/// # Safety
+///
+/// This function makes assumptions about the layout and location of memory referenced by
+/// [`AccountInfo`] fields. It should only be called for instances of `AccountInfo` that were
+/// created by the runtime and received in the `process_instruction` entrypoint of a program.
+pub fn resize_account<'a>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_len: usize,
+    zero: ZeroPolicy,
+) -> ProgramResult {
+    assert_is_solana!("resize_account", account);
+
+    let old_len = account.data_len();
+
+    {
+        let mut data = realloc_account_mut(account, new_len)?;
+
+        if zero == ZeroPolicy::Zero && new_len > old_len {
+            crate::fill_fast(&mut data[old_len..], 0);
+        }
+    }
+
+    let rent = Rent::get()?;
+    let required_lamports = rent.minimum_balance(new_len).max(1);
+
+    let lamports = account.lamports();
+
+    if lamports < required_lamports {
+        let top_up = required_lamports - lamports;
+
+        if crate::pubkeys_eq(payer.owner, account.owner) {
+            transfer_lamports(payer, account, top_up)?;
+        } else {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, top_up),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+    } else if new_len < old_len {
+        let released = rent.minimum_balance(old_len).saturating_sub(required_lamports);
+        let refund = released.min(lamports - required_lamports);
+
+        if refund > 0 {
+            transfer_lamports(account, payer, refund)?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Transfer lamports from `src` to `dst`, where `src` is owned by the executing program.
 pub fn transfer_lamports(src: &AccountInfo, dst: &AccountInfo, amount: u64) -> ProgramResult {
     assert_is_solana!("transfer_lamports");
@@ -167,3 +319,258 @@ pub fn transfer_lamports(src: &AccountInfo, dst: &AccountInfo, amount: u64) -> P
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use solana_program::program_error::ProgramError;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::rent::Rent;
+    use solana_program::system_program;
+
+    use super::*;
+    use crate::test_runtime::{process_instruction, Account};
+
+    struct Setup {
+        program_id: Pubkey,
+        instruction: Instruction,
+        accounts: Vec<(Pubkey, Account)>,
+    }
+
+    /// An account owned by the program with `len` bytes of data, a payer owned by `payer_owner`,
+    /// and the system program.
+    fn setup(len: usize, payer_owner: Option<Pubkey>, payer_signs: bool) -> Setup {
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let payer_owner = payer_owner.unwrap_or(program_id);
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new(account, false),
+                AccountMeta::new(payer, payer_signs),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+        );
+
+        let mut state = Account::new(Rent::default().minimum_balance(len), len, &program_id);
+        state.data.iter_mut().enumerate().for_each(|(i, byte)| *byte = i as u8 | 1);
+
+        let accounts = vec![
+            (account, state),
+            (payer, Account::new(1_000_000_000, 0, &payer_owner)),
+            (system_program::ID, Account::default()),
+        ];
+
+        Setup { program_id, instruction, accounts }
+    }
+
+    fn resize(setup: &mut Setup, resizes: &[(usize, ZeroPolicy)]) -> ProgramResult {
+        process_instruction(&setup.instruction, &mut setup.accounts, |_, accounts, _| {
+            let [account, payer, system_program] = accounts else { unreachable!() };
+
+            for (new_len, zero) in resizes {
+                resize_account(account, payer, system_program, *new_len, *zero)?;
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn resize_grow_from_program_payer() {
+        let mut setup = setup(10, None, false);
+
+        resize(&mut setup, &[(100, ZeroPolicy::Skip)]).unwrap();
+
+        let rent = Rent::default();
+        let top_up = rent.minimum_balance(100) - rent.minimum_balance(10);
+        assert_eq!(setup.accounts[0].1.data.len(), 100);
+        assert_eq!(setup.accounts[0].1.lamports, rent.minimum_balance(100));
+        assert_eq!(setup.accounts[0].1.owner, setup.program_id);
+        assert_eq!(setup.accounts[1].1.lamports, 1_000_000_000 - top_up);
+    }
+
+    #[test]
+    fn resize_grow_from_system_payer() {
+        let mut setup = setup(10, Some(system_program::ID), true);
+
+        resize(&mut setup, &[(100, ZeroPolicy::Skip)]).unwrap();
+
+        assert_eq!(setup.accounts[0].1.lamports, Rent::default().minimum_balance(100));
+    }
+
+    #[test]
+    fn resize_grow_from_system_payer_without_signature() {
+        let mut setup = setup(10, Some(system_program::ID), false);
+
+        let result = resize(&mut setup, &[(100, ZeroPolicy::Skip)]);
+
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(setup.accounts[0].1.data.len(), 10);
+    }
+
+    #[test]
+    fn resize_grow_with_insufficient_funds() {
+        let mut setup = setup(10, None, false);
+        setup.accounts[1].1.lamports = 1;
+
+        let result = resize(&mut setup, &[(100, ZeroPolicy::Skip)]);
+
+        assert_eq!(result, Err(ProgramError::InsufficientFunds));
+    }
+
+    #[test]
+    fn resize_grow_beyond_limit() {
+        let mut setup = setup(10, None, false);
+
+        let result =
+            resize(&mut setup, &[(10 + MAX_PERMITTED_DATA_INCREASE + 1, ZeroPolicy::Skip)]);
+        assert_eq!(result, Err(ProgramError::InvalidRealloc));
+
+        // The limit applies to the total increase across resizes.
+        let result = resize(
+            &mut setup,
+            &[
+                (10 + MAX_PERMITTED_DATA_INCREASE, ZeroPolicy::Skip),
+                (10, ZeroPolicy::Skip),
+                (11 + MAX_PERMITTED_DATA_INCREASE, ZeroPolicy::Skip),
+            ],
+        );
+        assert_eq!(result, Err(ProgramError::InvalidRealloc));
+    }
+
+    #[test]
+    fn resize_shrink_refunds_payer() {
+        let mut setup = setup(100, None, false);
+        setup.accounts[0].1.lamports += 5;
+
+        resize(&mut setup, &[(10, ZeroPolicy::Skip)]).unwrap();
+
+        let rent = Rent::default();
+        let refund = rent.minimum_balance(100) - rent.minimum_balance(10);
+        assert_eq!(setup.accounts[0].1.data.len(), 10);
+        assert_eq!(setup.accounts[0].1.lamports, rent.minimum_balance(10) + 5);
+        assert_eq!(setup.accounts[1].1.lamports, 1_000_000_000 + refund);
+    }
+
+    #[test]
+    fn resize_zero_policy() {
+        // Bytes exposed by growing within the same call are stale unless zeroed.
+        let mut stale = setup(10, None, false);
+        resize(&mut stale, &[(4, ZeroPolicy::Skip), (10, ZeroPolicy::Skip)]).unwrap();
+        assert_eq!(stale.accounts[0].1.data[4..], [5, 5, 7, 7, 9, 9]);
+
+        let mut zeroed = setup(10, None, false);
+        resize(&mut zeroed, &[(4, ZeroPolicy::Skip), (10, ZeroPolicy::Zero)]).unwrap();
+        assert_eq!(zeroed.accounts[0].1.data[..4], [1, 1, 3, 3]);
+        assert_eq!(zeroed.accounts[0].1.data[4..], [0; 6]);
+    }
+
+    fn close(
+        setup: &mut Setup,
+        mode: CloseMode,
+        then: impl FnOnce(&AccountInfo) -> ProgramResult,
+    ) -> ProgramResult {
+        process_instruction(&setup.instruction, &mut setup.accounts, |_, accounts, _| {
+            let [account, payer, _] = accounts else { unreachable!() };
+
+            close_account_secure(account, payer, mode)?;
+            then(account)
+        })
+    }
+
+    #[test]
+    fn close_secure_realloc() {
+        let mut setup = setup(10, None, false);
+        let lamports = setup.accounts[0].1.lamports;
+
+        close(&mut setup, CloseMode::Realloc, |account| {
+            // Growing the account again within the same call does not expose the old data.
+            let data = realloc_account_mut(account, 10)?;
+            assert_eq!(*data, [0; 10]);
+            drop(data);
+            realloc_account_mut(account, 0)?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(setup.accounts[0].1, Account::new(0, 0, &system_program::ID));
+        assert_eq!(setup.accounts[1].1.lamports, 1_000_000_000 + lamports);
+    }
+
+    #[test]
+    fn close_secure_marker() {
+        let mut setup = setup(10, None, false);
+        let lamports = setup.accounts[0].1.lamports;
+
+        close(&mut setup, CloseMode::Marker, |account| {
+            assert_eq!(check_not_closed(account), Err(ProgramError::InvalidAccountData));
+            Ok(())
+        })
+        .unwrap();
+
+        let account = &setup.accounts[0].1;
+        assert_eq!(account.lamports, 0);
+        assert_eq!(account.owner, setup.program_id);
+        assert_eq!(account.data[..8], CLOSED_ACCOUNT_DISCRIMINATOR);
+        assert_eq!(account.data[8..], [0; 2]);
+        assert_eq!(setup.accounts[1].1.lamports, 1_000_000_000 + lamports);
+    }
+
+    #[test]
+    fn close_secure_marker_revived() {
+        let mut setup = setup(10, Some(system_program::ID), true);
+
+        process_instruction(&setup.instruction, &mut setup.accounts, |_, accounts, _| {
+            let [account, payer, _] = accounts else { unreachable!() };
+
+            close_account_secure(account, payer, CloseMode::Marker)?;
+
+            // Refunding the account later in the same transaction, before the runtime purges it,
+            // leaves it marked as closed.
+            invoke(&system_instruction::transfer(payer.key, account.key, 1_000), accounts)?;
+            assert_eq!(account.lamports(), 1_000);
+            assert_eq!(check_not_closed(account), Err(ProgramError::InvalidAccountData));
+
+            check_not_closed(payer)
+        })
+        .unwrap();
+
+        let account = &setup.accounts[0].1;
+        assert_eq!(account.lamports, 1_000);
+        assert_eq!(account.data[..8], CLOSED_ACCOUNT_DISCRIMINATOR);
+    }
+
+    #[test]
+    fn close_secure_into_itself() {
+        let mut setup = setup(10, None, false);
+        setup.instruction.accounts[1] = setup.instruction.accounts[0].clone();
+
+        let result = close(&mut setup, CloseMode::Realloc, |_| Ok(()));
+
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn close_secure_marker_too_small() {
+        let mut setup = setup(7, None, false);
+
+        let result = close(&mut setup, CloseMode::Marker, |_| Ok(()));
+
+        assert_eq!(result, Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(setup.accounts[0].1.data, [1, 1, 3, 3, 5, 5, 7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "realloc_account: account not created by the runtime")]
+    fn realloc_outside_runtime() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, [0; 8]);
+        let account =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+        let _ = realloc_account_mut(&account, 4);
+    }
+}
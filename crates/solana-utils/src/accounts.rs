@@ -0,0 +1,194 @@
+//! Declarative validation of the accounts of an instruction.
+
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub use solana_utils_macro::Accounts;
+
+/// A struct of accounts which can be parsed and validated from the accounts of an instruction.
+///
+/// # Derive
+///
+/// The derive macro takes the accounts in the order of the fields, which are
+/// `&'a AccountInfo<'info>`. The struct can have a single lifetime parameter, which is used for
+/// both lifetimes, or two lifetime parameters, the first of which is the lifetime of the accounts
+/// slice.
+///
+/// Each field can be validated with `#[account(..)]`, which accepts:
+///
+/// - `signer`, which fails with [`ProgramError::MissingRequiredSignature`];
+/// - `writable`, which fails with [`ProgramError::InvalidArgument`];
+/// - `executable`, which fails with [`ProgramError::InvalidAccountData`];
+/// - `owner = <expr>`, which fails with [`ProgramError::IllegalOwner`];
+/// - `address = <expr>`, which fails with [`ProgramError::InvalidArgument`].
+///
+/// The expressions can be a `Pubkey` or a `&Pubkey`, and can refer to `program_id`. Pubkeys are
+/// compared with [`pubkeys_eq`](crate::pubkeys_eq). The name of the field is logged when a check
+/// fails, or when an account is missing, which fails with [`ProgramError::NotEnoughAccountKeys`].
+///
+/// A field of type `Option<&'a AccountInfo<'info>>` is an optional account, which is `None` if the
+/// accounts end before it or, as in Anchor, if its key is the program id. Its constraints are only
+/// checked if it is present. The last field can be of type `&'a [AccountInfo<'info>]` to receive
+/// the remaining accounts, which are otherwise ignored. Fields of any other type are rejected.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_program::system_program;
+/// use solana_utils::accounts::Accounts;
+///
+/// #[derive(Accounts)]
+/// struct Deposit<'a, 'info> {
+///     #[account(signer, writable)]
+///     user: &'a AccountInfo<'info>,
+///     #[account(writable, owner = program_id)]
+///     vault: &'a AccountInfo<'info>,
+///     #[account(address = system_program::ID)]
+///     system_program: &'a AccountInfo<'info>,
+///     referrer: Option<&'a AccountInfo<'info>>,
+/// }
+///
+/// # fn account<'a>(
+/// #     key: &'a Pubkey,
+/// #     owner: &'a Pubkey,
+/// #     lamports: &'a mut u64,
+/// #     is_signer: bool,
+/// #     is_writable: bool,
+/// # ) -> AccountInfo<'a> {
+/// #     let executable = *key == system_program::ID;
+/// #     AccountInfo::new(key, is_signer, is_writable, lamports, &mut [], owner, executable, 0)
+/// # }
+/// let program_id = Pubkey::new_unique();
+/// # let [user, vault, loader] = [(); 3].map(|_| Pubkey::new_unique());
+/// # let mut lamports = [0; 3];
+/// # let [a, b, c] = &mut lamports;
+/// let mut accounts = [
+///     account(&user, &system_program::ID, a, true, true),
+///     account(&vault, &program_id, b, false, true),
+///     account(&system_program::ID, &loader, c, false, false),
+/// ];
+///
+/// let deposit = Deposit::try_accounts(&program_id, &accounts).unwrap();
+/// assert!(deposit.referrer.is_none());
+///
+/// // Logs "Error: Account vault (..) is not writable".
+/// accounts[1].is_writable = false;
+/// assert!(Deposit::try_accounts(&program_id, &accounts).is_err());
+/// ```
+///
+/// ```compile_fail
+/// # use solana_program::account_info::AccountInfo;
+/// # use solana_utils::accounts::Accounts;
+/// #
+/// #[derive(Accounts)]
+/// struct Transfer<'a, 'info> {
+///     from: &'a AccountInfo<'info>,
+///     amount: u64,
+/// }
+/// ```
+pub trait Accounts<'a, 'info>: Sized {
+    /// Parses and validates the accounts, in the order of the fields.
+    fn try_accounts(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError>;
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use solana_program::account_info::AccountInfo;
+    pub use solana_program::program_error::ProgramError;
+    pub use solana_program::pubkey::Pubkey;
+
+    use crate::{log, pubkeys_eq};
+
+    #[cold]
+    pub fn missing_account(field: &'static str) -> ProgramError {
+        log!("Error: Missing account {}", field);
+        ProgramError::NotEnoughAccountKeys
+    }
+
+    #[inline(always)]
+    pub fn check_signer(account: &AccountInfo, field: &'static str) -> Result<(), ProgramError> {
+        if !account.is_signer {
+            return Err(not_signer(account, field));
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn check_writable(account: &AccountInfo, field: &'static str) -> Result<(), ProgramError> {
+        if !account.is_writable {
+            return Err(not_writable(account, field));
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn check_executable(
+        account: &AccountInfo,
+        field: &'static str,
+    ) -> Result<(), ProgramError> {
+        if !account.executable {
+            return Err(not_executable(account, field));
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn check_owner(
+        account: &AccountInfo,
+        owner: &Pubkey,
+        field: &'static str,
+    ) -> Result<(), ProgramError> {
+        if !pubkeys_eq(account.owner, owner) {
+            return Err(wrong_owner(account, field));
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn check_address(
+        account: &AccountInfo,
+        address: &Pubkey,
+        field: &'static str,
+    ) -> Result<(), ProgramError> {
+        if !pubkeys_eq(account.key, address) {
+            return Err(wrong_address(account, field));
+        }
+        Ok(())
+    }
+
+    #[cold]
+    fn not_signer(account: &AccountInfo, field: &'static str) -> ProgramError {
+        log!("Error: Account {} ({}) is not a signer", field, account.key);
+        ProgramError::MissingRequiredSignature
+    }
+
+    #[cold]
+    fn not_writable(account: &AccountInfo, field: &'static str) -> ProgramError {
+        log!("Error: Account {} ({}) is not writable", field, account.key);
+        ProgramError::InvalidArgument
+    }
+
+    #[cold]
+    fn not_executable(account: &AccountInfo, field: &'static str) -> ProgramError {
+        log!("Error: Account {} ({}) is not executable", field, account.key);
+        ProgramError::InvalidAccountData
+    }
+
+    #[cold]
+    fn wrong_owner(account: &AccountInfo, field: &'static str) -> ProgramError {
+        log!("Error: Account {} ({}) has the wrong owner {}", field, account.key, account.owner);
+        ProgramError::IllegalOwner
+    }
+
+    #[cold]
+    fn wrong_address(account: &AccountInfo, field: &'static str) -> ProgramError {
+        log!("Error: Account {} has the wrong address {}", field, account.key);
+        ProgramError::InvalidArgument
+    }
+}
@@ -0,0 +1,232 @@
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+
+use super::{capacity, check_len, check_stored_len, grow, read_u64, write_u64, META_LEN};
+use crate::{fill_fast, log};
+
+/// A bitmap stored in account data.
+///
+/// The metadata is the length of the bitmap in bits, followed by the bits packed eight per byte,
+/// least significant bit first. See the [module documentation](crate::collections) for details.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::program_error::ProgramError;
+/// use solana_utils::collections::AccountBitmap;
+///
+/// fn claim_slot(slots: &AccountInfo) -> Result<usize, ProgramError> {
+///     let slots = AccountBitmap::new(slots, 0)?;
+///
+///     let slot = match slots.first_zero()? {
+///         Some(slot) => slot,
+///         None => {
+///             let slot = slots.len()?;
+///             slots.resize(slot + 1)?;
+///             slot
+///         }
+///     };
+///     slots.set(slot, true)?;
+///
+///     Ok(slot)
+/// }
+/// ```
+pub struct AccountBitmap<'a, 'info> {
+    account: &'a AccountInfo<'info>,
+    offset: usize,
+}
+
+impl<'a, 'info> AccountBitmap<'a, 'info> {
+    /// Returns the space needed for a bitmap of `len` bits.
+    pub const fn space(len: usize) -> usize {
+        META_LEN + len.div_ceil(8)
+    }
+
+    /// Creates a bitmap stored at `offset` in the data of `account`.
+    pub fn new(account: &'a AccountInfo<'info>, offset: usize) -> Result<Self, ProgramError> {
+        check_len(account, &account.try_borrow_data()?, offset)?;
+
+        Ok(AccountBitmap { account, offset })
+    }
+
+    /// Returns the number of bits in the bitmap.
+    pub fn len(&self) -> Result<usize, ProgramError> {
+        self.stored_len(&self.account.try_borrow_data()?)
+    }
+
+    /// Returns `true` if the bitmap has no bits.
+    pub fn is_empty(&self) -> Result<bool, ProgramError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the bit at `index`, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Result<Option<bool>, ProgramError> {
+        let data = self.account.try_borrow_data()?;
+
+        if index >= self.stored_len(&data)? {
+            return Ok(None);
+        }
+
+        Ok(Some(data[self.start() + index / 8] & (1 << (index % 8)) != 0))
+    }
+
+    /// Sets the bit at `index` to `value`.
+    pub fn set(&self, index: usize, value: bool) -> ProgramResult {
+        let mut data = self.account.try_borrow_mut_data()?;
+        let len = self.stored_len(&data)?;
+
+        if index >= len {
+            return Err(self.out_of_bounds(index, len));
+        }
+
+        let byte = &mut data[self.start() + index / 8];
+        if value {
+            *byte |= 1 << (index % 8);
+        } else {
+            *byte &= !(1 << (index % 8));
+        }
+
+        Ok(())
+    }
+
+    /// Resizes the bitmap to `len` bits, growing the account data if needed.
+    ///
+    /// Added bits are cleared. Removed bits are cleared as well, but the account data is not
+    /// shrunk.
+    pub fn resize(&self, len: usize) -> ProgramResult {
+        let old_len = self.len()?;
+        let bytes = len.div_ceil(8);
+
+        if self.start() + bytes > self.account.try_borrow_data()?.len() {
+            grow(self.account, self.start(), 1, bytes)?;
+        }
+
+        let mut data = self.account.try_borrow_mut_data()?;
+        let (from, to) = if len > old_len { (old_len, len) } else { (len, old_len) };
+        self.clear_range(&mut data, from, to);
+        write_u64(&mut data, self.offset, len as u64);
+
+        Ok(())
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> Result<usize, ProgramError> {
+        let data = self.account.try_borrow_data()?;
+        let len = self.stored_len(&data)?;
+
+        // Bits past the length are always clear.
+        Ok(data[self.start()..self.start() + len.div_ceil(8)]
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum())
+    }
+
+    /// Returns the index of the first clear bit, or `None` if all bits are set.
+    pub fn first_zero(&self) -> Result<Option<usize>, ProgramError> {
+        let data = self.account.try_borrow_data()?;
+        let len = self.stored_len(&data)?;
+
+        Ok(data[self.start()..self.start() + len.div_ceil(8)]
+            .iter()
+            .position(|byte| *byte != u8::MAX)
+            .map(|byte| byte * 8 + data[self.start() + byte].trailing_ones() as usize)
+            .filter(|index| *index < len))
+    }
+
+    /// Returns the length stored in the metadata, checked against the capacity.
+    #[inline]
+    fn stored_len(&self, data: &[u8]) -> Result<usize, ProgramError> {
+        check_len(self.account, data, self.offset)?;
+
+        let len = read_u64(data, self.offset) as usize;
+        check_stored_len(self.account, len, capacity(data, self.start(), 1).saturating_mul(8))?;
+
+        Ok(len)
+    }
+
+    #[inline]
+    fn start(&self) -> usize {
+        self.offset + META_LEN
+    }
+
+    /// Clears the bits from `from` to `to`.
+    fn clear_range(&self, data: &mut [u8], from: usize, to: usize) {
+        let mut index = from;
+
+        while index < to && index % 8 != 0 {
+            data[self.start() + index / 8] &= !(1 << (index % 8));
+            index += 1;
+        }
+
+        if index < to {
+            let range = self.start() + index / 8..self.start() + to.div_ceil(8);
+            fill_fast(&mut data[range], 0);
+        }
+    }
+
+    #[cold]
+    fn out_of_bounds(&self, index: usize, len: usize) -> ProgramError {
+        log!("Error: Index {} is out of bounds of {} bits in {}", index, len, self.account.key);
+        ProgramError::InvalidArgument
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::tests::run;
+
+    #[test]
+    fn operations() {
+        let (result, data) = run(&[0; 8], |account| {
+            let bitmap = AccountBitmap::new(account, 0)?;
+            assert_eq!(bitmap.first_zero(), Ok(None));
+
+            bitmap.resize(20)?;
+            for i in 0..10 {
+                bitmap.set(i, true)?;
+            }
+            bitmap.set(3, false)?;
+            assert_eq!(bitmap.first_zero(), Ok(Some(3)));
+            assert_eq!(bitmap.count_ones(), Ok(9));
+            assert_eq!(bitmap.set(20, true), Err(ProgramError::InvalidArgument));
+            assert_eq!(bitmap.get(20), Ok(None));
+
+            // Removed bits are cleared, so they read as clear once added back.
+            bitmap.resize(5)?;
+            assert_eq!(bitmap.count_ones(), Ok(4));
+            bitmap.resize(12)?;
+            assert_eq!(bitmap.get(8), Ok(Some(false)));
+            assert_eq!(bitmap.count_ones(), Ok(4));
+
+            for i in 0..12 {
+                bitmap.set(i, true)?;
+            }
+            assert_eq!(bitmap.first_zero(), Ok(None));
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data, [12, 0, 0, 0, 0, 0, 0, 0, 0xff, 0x0f, 0]);
+    }
+
+    #[test]
+    fn len_beyond_capacity() {
+        let (result, _) = run(&[0; 8], |account| {
+            let bitmap = AccountBitmap::new(account, 0)?;
+            bitmap.resize(9)?;
+
+            // The account data is shrunk by other code.
+            crate::realloc_account_mut(account, 9)?;
+            assert_eq!(bitmap.get(0), Err(ProgramError::InvalidAccountData));
+            assert_eq!(bitmap.count_ones(), Err(ProgramError::InvalidAccountData));
+            bitmap.set(0, true)
+        });
+
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+}
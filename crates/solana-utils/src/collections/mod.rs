@@ -0,0 +1,251 @@
+//! Zero-copy collections stored in account data.
+//!
+//! Each collection is stored at an offset in the account data, usually after a header such as
+//! the one loaded by [`AccountLoader`](crate::AccountLoader), and extends to the end of the data.
+//! It starts with 8 bytes of metadata, zero for an empty collection, so a collection does not
+//! need to be initialized in a freshly created account. Its capacity is given by the length of
+//! the account data, which grows with [`realloc_account_mut`] as elements are added.
+//!
+//! Account data can only grow by [`MAX_PERMITTED_DATA_INCREASE`] bytes within an instruction, so
+//! collections are grown to exactly the required capacity. Growing does not transfer the lamports
+//! needed to keep the account rent-exempt, which can be done afterwards with
+//! [`resize_account`](crate::resize_account) to the current length of the data.
+//!
+//! The account data is borrowed for the duration of each method, which fails if it is already
+//! borrowed incompatibly. The stored metadata is checked against the length of the account data on
+//! each access, so a collection whose account was shrunk or overwritten by other code fails with
+//! [`ProgramError::InvalidAccountData`] rather than panicking.
+//!
+//! # Safety
+//!
+//! Growing makes assumptions about the layout and location of memory referenced by
+//! [`AccountInfo`] fields, as [`realloc_account_mut`] does. Collections should only be grown for
+//! instances of `AccountInfo` that were created by the runtime and received in the
+//! `process_instruction` entrypoint of a program.
+
+use std::ops::Range;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE};
+use solana_program::program_error::ProgramError;
+
+use crate::{log, realloc_account_mut};
+
+mod bitmap;
+mod ring_buffer;
+mod vec;
+
+pub use self::bitmap::AccountBitmap;
+pub use self::ring_buffer::AccountRingBuffer;
+pub use self::vec::AccountVec;
+
+/// The length of the metadata stored before the elements of a collection.
+const META_LEN: usize = 8;
+
+/// Checks that `data`, the data of `account`, can hold the metadata of a collection at `offset`.
+fn check_len(account: &AccountInfo, data: &[u8], offset: usize) -> ProgramResult {
+    if data.len() < offset + META_LEN {
+        log!(
+            "Error: Account {} has {} bytes of data, expected at least {}",
+            account.key,
+            data.len(),
+            offset + META_LEN,
+        );
+
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    Ok(())
+}
+
+/// Returns the number of elements of `size` bytes that fit in `data` from `start`.
+#[inline]
+fn capacity(data: &[u8], start: usize, size: usize) -> usize {
+    data.len().saturating_sub(start) / size
+}
+
+/// Checks that the length stored in the metadata of a collection fits in its capacity, which
+/// fails if the metadata is corrupt or the account data was shrunk by other code.
+#[inline]
+fn check_stored_len(account: &AccountInfo, len: usize, capacity: usize) -> ProgramResult {
+    if len > capacity {
+        return Err(invalid_len(account, len, capacity));
+    }
+
+    Ok(())
+}
+
+#[cold]
+fn invalid_len(account: &AccountInfo, len: usize, capacity: usize) -> ProgramError {
+    log!(
+        "Error: Account {} stores a collection of {} elements, but has a capacity of {}",
+        account.key,
+        len,
+        capacity,
+    );
+    ProgramError::InvalidAccountData
+}
+
+/// Grows the account data to hold `capacity` elements of `size` bytes from `start`.
+fn grow(account: &AccountInfo, start: usize, size: usize, capacity: usize) -> ProgramResult {
+    assert_is_solana!("grow_collection", account);
+
+    let new_len = capacity
+        .checked_mul(size)
+        .and_then(|len| len.checked_add(start))
+        .ok_or(ProgramError::InvalidRealloc)?;
+
+    // SAFETY: See the safety section of the module documentation.
+    let original_len = unsafe { account.original_data_len() };
+    if new_len.saturating_sub(original_len) > MAX_PERMITTED_DATA_INCREASE {
+        log!(
+            "Error: Account {} cannot grow to {} bytes within an instruction, the maximum is {}",
+            account.key,
+            new_len,
+            original_len + MAX_PERMITTED_DATA_INCREASE,
+        );
+
+        return Err(ProgramError::InvalidRealloc);
+    }
+
+    realloc_account_mut(account, new_len)?;
+
+    Ok(())
+}
+
+/// Copies the bytes in `src` to `dst` within `data` using `sol_memmove`, so the ranges can
+/// overlap.
+#[inline]
+fn move_within(data: &mut [u8], src: Range<usize>, dst: usize) {
+    assert!(src.start <= src.end && src.end <= data.len() && dst + src.len() <= data.len());
+
+    let ptr = data.as_mut_ptr();
+    // SAFETY: Both ranges are in bounds of `data`, as checked above.
+    unsafe { crate::syscalls::memmove(ptr.add(dst), ptr.add(src.start), src.len()) }
+}
+
+#[inline]
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[inline]
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+#[inline]
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[inline]
+fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use solana_program::pubkey::Pubkey;
+
+    use super::*;
+    use crate::test_runtime::{process_instruction, Account};
+
+    /// Processes an instruction with a writable account holding `data`, and returns the data the
+    /// account holds afterwards.
+    pub(super) fn run(
+        data: &[u8],
+        test: impl FnOnce(&AccountInfo) -> ProgramResult,
+    ) -> (ProgramResult, Vec<u8>) {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(key, false)]);
+
+        let mut account = Account::new(1_000_000_000, 0, &program_id);
+        account.data = data.to_vec();
+        let mut accounts = vec![(key, account)];
+
+        let result =
+            process_instruction(&instruction, &mut accounts, |_, accounts, _| test(&accounts[0]));
+
+        (result, accounts.swap_remove(0).1.data)
+    }
+
+    #[test]
+    fn data_too_small() {
+        let (result, _) = run(&[0; 11], |account| {
+            assert_eq!(
+                AccountBitmap::new(account, 4).err(),
+                Some(ProgramError::AccountDataTooSmall)
+            );
+            assert_eq!(
+                AccountRingBuffer::<u8>::new(account, 4).err(),
+                Some(ProgramError::AccountDataTooSmall),
+            );
+            AccountVec::<u8>::new(account, 4).map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn data_shrunk_below_metadata() {
+        let (result, _) = run(&[0; META_LEN], |account| {
+            let vec = AccountVec::<u8>::new(account, 0)?;
+            realloc_account_mut(account, META_LEN - 1)?;
+            vec.len().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn grow_limit() {
+        let (result, data) = run(&[0; META_LEN], |account| {
+            let vec = AccountVec::<[u8; 1024]>::new(account, 0)?;
+            vec.reserve(MAX_PERMITTED_DATA_INCREASE / 1024)?;
+            assert_eq!(vec.capacity(), Ok(MAX_PERMITTED_DATA_INCREASE / 1024));
+
+            // The account data can grow no further within the instruction.
+            assert_eq!(
+                vec.reserve(MAX_PERMITTED_DATA_INCREASE / 1024 + 1),
+                Err(ProgramError::InvalidRealloc)
+            );
+            assert_eq!(vec.reserve(usize::MAX), Err(ProgramError::InvalidRealloc));
+            assert_eq!(
+                AccountBitmap::new(account, 0)?.resize(usize::MAX),
+                Err(ProgramError::InvalidRealloc)
+            );
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data.len(), META_LEN + MAX_PERMITTED_DATA_INCREASE);
+    }
+
+    #[test]
+    fn borrowed() {
+        let (result, _) = run(&[0; 16], |account| {
+            let vec = AccountVec::<u8>::new(account, 0)?;
+            let ring_buffer = AccountRingBuffer::<u8>::new(account, 0)?;
+
+            let data = account.try_borrow_mut_data()?;
+            assert_eq!(vec.len(), Err(ProgramError::AccountBorrowFailed));
+            assert_eq!(ring_buffer.capacity(), Err(ProgramError::AccountBorrowFailed));
+            drop(data);
+
+            let elements = vec.as_slice()?;
+            assert_eq!(vec.push(1), Err(ProgramError::AccountBorrowFailed));
+            assert_eq!(ring_buffer.push_back(1), Err(ProgramError::AccountBorrowFailed));
+            assert_eq!(vec.len(), Ok(0));
+            drop(elements);
+
+            vec.push(1)
+        });
+
+        assert_eq!(result, Ok(()));
+    }
+}
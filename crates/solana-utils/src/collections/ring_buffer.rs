@@ -0,0 +1,345 @@
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::Range;
+
+use bytemuck::Pod;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+
+use super::{
+    capacity, check_len, check_stored_len, grow, move_within, read_u32, write_u32, META_LEN,
+};
+use crate::{copy_from_slice_fast, log};
+
+/// A double-ended ring buffer of [`Pod`] elements stored in account data.
+///
+/// The metadata is the index of the first element and the length of the buffer, followed by the
+/// elements. See the [module documentation](crate::collections) for details.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_utils::collections::AccountRingBuffer;
+///
+/// fn record_price(history: &AccountInfo, price: u64) -> ProgramResult {
+///     // Keep the most recent prices, overwriting the oldest once the buffer is full.
+///     let history = AccountRingBuffer::<u64>::new(history, 0)?;
+///     history.push_overwrite(price)?;
+///     Ok(())
+/// }
+/// ```
+pub struct AccountRingBuffer<'a, 'info, T> {
+    account: &'a AccountInfo<'info>,
+    offset: usize,
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'info, T: Pod> AccountRingBuffer<'a, 'info, T> {
+    /// Returns the space needed for a ring buffer with a capacity of `capacity` elements.
+    pub const fn space(capacity: usize) -> usize {
+        META_LEN + capacity * size_of::<T>()
+    }
+
+    /// Creates a ring buffer stored at `offset` in the data of `account`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is zero-sized.
+    pub fn new(account: &'a AccountInfo<'info>, offset: usize) -> Result<Self, ProgramError> {
+        assert!(size_of::<T>() > 0, "zero-sized elements are not supported");
+        check_len(account, &account.try_borrow_data()?, offset)?;
+
+        Ok(AccountRingBuffer { account, offset, marker: PhantomData })
+    }
+
+    /// Returns the number of elements in the ring buffer.
+    pub fn len(&self) -> Result<usize, ProgramError> {
+        let (_, len, _) = self.meta(&self.account.try_borrow_data()?)?;
+        Ok(len)
+    }
+
+    /// Returns `true` if the ring buffer has no elements.
+    pub fn is_empty(&self) -> Result<bool, ProgramError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the number of elements the ring buffer can hold without growing the account data.
+    pub fn capacity(&self) -> Result<usize, ProgramError> {
+        Ok(capacity(&self.account.try_borrow_data()?, self.offset + META_LEN, size_of::<T>()))
+    }
+
+    /// Returns the element at `index`, counting from the front.
+    pub fn get(&self, index: usize) -> Result<Option<T>, ProgramError> {
+        let data = self.account.try_borrow_data()?;
+        let (head, len, capacity) = self.meta(&data)?;
+
+        if index >= len {
+            return Ok(None);
+        }
+
+        Ok(Some(bytemuck::pod_read_unaligned(&data[self.range(head + index, capacity)])))
+    }
+
+    /// Appends an element to the back, growing the account data if the ring buffer is full.
+    pub fn push_back(&self, value: T) -> ProgramResult {
+        self.reserve(1)?;
+
+        let mut data = self.account.try_borrow_mut_data()?;
+        let (head, len, capacity) = self.meta(&data)?;
+
+        let range = self.range(head + len, capacity);
+        copy_from_slice_fast(&mut data[range], bytemuck::bytes_of(&value));
+        self.set_meta(&mut data, head, len + 1, capacity);
+
+        Ok(())
+    }
+
+    /// Appends an element to the back, removing and returning the front element if the ring
+    /// buffer is full, so that the account data only grows if the capacity is zero.
+    pub fn push_overwrite(&self, value: T) -> Result<Option<T>, ProgramError> {
+        if self.capacity()? == 0 {
+            self.push_back(value)?;
+            return Ok(None);
+        }
+
+        let mut data = self.account.try_borrow_mut_data()?;
+        let (head, len, capacity) = self.meta(&data)?;
+
+        if len < capacity {
+            let range = self.range(head + len, capacity);
+            copy_from_slice_fast(&mut data[range], bytemuck::bytes_of(&value));
+            self.set_meta(&mut data, head, len + 1, capacity);
+
+            return Ok(None);
+        }
+
+        let range = self.range(head, capacity);
+        let front = bytemuck::pod_read_unaligned(&data[range.clone()]);
+        copy_from_slice_fast(&mut data[range], bytemuck::bytes_of(&value));
+        self.set_meta(&mut data, head + 1, len, capacity);
+
+        Ok(Some(front))
+    }
+
+    /// Removes the front element and returns it.
+    pub fn pop_front(&self) -> Result<Option<T>, ProgramError> {
+        let mut data = self.account.try_borrow_mut_data()?;
+        let (head, len, capacity) = self.meta(&data)?;
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let front = bytemuck::pod_read_unaligned(&data[self.range(head, capacity)]);
+        self.set_meta(&mut data, head + 1, len - 1, capacity);
+
+        Ok(Some(front))
+    }
+
+    /// Removes the back element and returns it.
+    pub fn pop_back(&self) -> Result<Option<T>, ProgramError> {
+        let mut data = self.account.try_borrow_mut_data()?;
+        let (head, len, capacity) = self.meta(&data)?;
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let back = bytemuck::pod_read_unaligned(&data[self.range(head + len - 1, capacity)]);
+        self.set_meta(&mut data, head, len - 1, capacity);
+
+        Ok(Some(back))
+    }
+
+    /// Removes all elements.
+    pub fn clear(&self) -> ProgramResult {
+        let mut data = self.account.try_borrow_mut_data()?;
+        check_len(self.account, &data, self.offset)?;
+        self.set_meta(&mut data, 0, 0, 0);
+
+        Ok(())
+    }
+
+    /// Grows the account data, if needed, to hold at least `additional` more elements.
+    ///
+    /// If the elements wrap around the end of the buffer, the elements from the front to the end
+    /// are moved to the new end with `sol_memmove`.
+    pub fn reserve(&self, additional: usize) -> ProgramResult {
+        let capacity = self.capacity()?;
+        let required = self.len()?.saturating_add(additional);
+
+        if required <= capacity {
+            return Ok(());
+        }
+
+        grow(self.account, self.offset + META_LEN, size_of::<T>(), required)?;
+
+        let mut data = self.account.try_borrow_mut_data()?;
+        let (head, len, new_capacity) = self.meta(&data)?;
+
+        if head + len > capacity {
+            let new_head = head + (new_capacity - capacity);
+            let size = size_of::<T>();
+            let start = self.offset + META_LEN;
+
+            move_within(
+                &mut data,
+                start + head * size..start + capacity * size,
+                start + new_head * size,
+            );
+            write_u32(&mut data, self.offset, new_head as u32);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the index of the front element and the length stored in the metadata, checked
+    /// against the capacity, and the capacity.
+    #[inline]
+    fn meta(&self, data: &[u8]) -> Result<(usize, usize, usize), ProgramError> {
+        check_len(self.account, data, self.offset)?;
+
+        let head = read_u32(data, self.offset) as usize;
+        let len = read_u32(data, self.offset + 4) as usize;
+        let capacity = capacity(data, self.offset + META_LEN, size_of::<T>());
+
+        check_stored_len(self.account, len, capacity)?;
+        if len > 0 && head >= capacity {
+            return Err(self.invalid_head(head, capacity));
+        }
+
+        Ok((head, len, capacity))
+    }
+
+    /// Sets the index of the front element, wrapped to `capacity`, and the length.
+    #[inline]
+    fn set_meta(&self, data: &mut [u8], head: usize, len: usize, capacity: usize) {
+        let head = if len == 0 { 0 } else { head % capacity };
+        write_u32(data, self.offset, head as u32);
+        write_u32(data, self.offset + 4, len as u32);
+    }
+
+    /// Returns the range of the element at the physical index `index`, wrapped to `capacity`.
+    #[inline]
+    fn range(&self, index: usize, capacity: usize) -> Range<usize> {
+        let start = self.offset + META_LEN + (index % capacity) * size_of::<T>();
+        start..start + size_of::<T>()
+    }
+
+    #[cold]
+    fn invalid_head(&self, head: usize, capacity: usize) -> ProgramError {
+        log!(
+            "Error: Account {} stores a ring buffer starting at {}, but has a capacity of {}",
+            self.account.key,
+            head,
+            capacity,
+        );
+        ProgramError::InvalidAccountData
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::tests::run;
+
+    fn elements(ring_buffer: &AccountRingBuffer<u16>) -> Result<Vec<u16>, ProgramError> {
+        (0..ring_buffer.len()?).map(|i| Ok(ring_buffer.get(i)?.unwrap())).collect()
+    }
+
+    #[test]
+    fn operations() {
+        let (result, _) = run(&[0; 8], |account| {
+            let ring_buffer = AccountRingBuffer::<u16>::new(account, 0)?;
+            assert_eq!(ring_buffer.pop_front(), Ok(None));
+            assert_eq!(ring_buffer.pop_back(), Ok(None));
+
+            for i in 0..4 {
+                ring_buffer.push_back(i)?;
+            }
+            assert_eq!(ring_buffer.capacity(), Ok(4));
+            assert_eq!(ring_buffer.pop_front(), Ok(Some(0)));
+            assert_eq!(ring_buffer.pop_back(), Ok(Some(3)));
+            assert_eq!(elements(&ring_buffer)?, [1, 2]);
+
+            ring_buffer.clear()?;
+            assert_eq!(ring_buffer.is_empty(), Ok(true));
+            assert_eq!(ring_buffer.get(0), Ok(None));
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn wrap_around() {
+        let (result, _) = run(&[0; 8], |account| {
+            let ring_buffer = AccountRingBuffer::<u16>::new(account, 0)?;
+
+            // Push overwrite grows the buffer only while its capacity is zero.
+            assert_eq!(ring_buffer.push_overwrite(0), Ok(None));
+            assert_eq!(ring_buffer.push_overwrite(1), Ok(Some(0)));
+            assert_eq!(ring_buffer.capacity(), Ok(1));
+
+            ring_buffer.reserve(3)?;
+            for i in 2..5 {
+                ring_buffer.push_back(i)?;
+            }
+            assert_eq!(ring_buffer.push_overwrite(5), Ok(Some(1)));
+            assert_eq!(ring_buffer.push_overwrite(6), Ok(Some(2)));
+            assert_eq!(elements(&ring_buffer)?, [3, 4, 5, 6]);
+            assert_eq!(ring_buffer.capacity(), Ok(4));
+
+            // The elements wrap around the end of the buffer, and are moved as it grows.
+            ring_buffer.push_back(7)?;
+            ring_buffer.reserve(3)?;
+            assert_eq!(elements(&ring_buffer)?, [3, 4, 5, 6, 7]);
+            assert_eq!(ring_buffer.capacity(), Ok(8));
+
+            assert_eq!(ring_buffer.pop_back(), Ok(Some(7)));
+            assert_eq!(ring_buffer.pop_front(), Ok(Some(3)));
+            ring_buffer.push_back(8)?;
+            assert_eq!(elements(&ring_buffer)?, [4, 5, 6, 8]);
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn invalid_meta() {
+        let meta =
+            |head: u32, len: u32| [&head.to_le_bytes()[..], &len.to_le_bytes(), &[0; 8]].concat();
+
+        // The head is out of bounds of the capacity.
+        let (result, _) = run(&meta(4, 1), |account| {
+            let ring_buffer = AccountRingBuffer::<u16>::new(account, 0)?;
+            assert_eq!(ring_buffer.get(0), Err(ProgramError::InvalidAccountData));
+            ring_buffer.push_back(1)
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        // The length is beyond the capacity.
+        let (result, _) = run(&meta(0, 5), |account| {
+            let ring_buffer = AccountRingBuffer::<u16>::new(account, 0)?;
+            assert_eq!(ring_buffer.len(), Err(ProgramError::InvalidAccountData));
+            ring_buffer.pop_front().map(drop)
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        // The account data is shrunk by other code.
+        let (result, _) = run(&meta(3, 2), |account| {
+            let ring_buffer = AccountRingBuffer::<u16>::new(account, 0)?;
+            assert_eq!(ring_buffer.pop_back(), Ok(Some(0)));
+
+            crate::realloc_account_mut(account, 12)?;
+            ring_buffer.push_overwrite(1).map(drop)
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+}
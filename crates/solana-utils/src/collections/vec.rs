@@ -0,0 +1,375 @@
+use std::cell::{Ref, RefMut};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::Range;
+
+use bytemuck::Pod;
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+
+use super::{
+    capacity, check_len, check_stored_len, grow, move_within, read_u64, write_u64, META_LEN,
+};
+use crate::{copy_from_slice_fast, log};
+
+/// A vector of [`Pod`] elements stored in account data.
+///
+/// The metadata is the length of the vector, followed by the elements. See the
+/// [module documentation](crate::collections) for details.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::collections::AccountVec;
+///
+/// fn add_participant(participants: &AccountInfo, participant: &Pubkey) -> ProgramResult {
+///     // The participants are stored after an 8-byte discriminator.
+///     let participants = AccountVec::<Pubkey>::new(participants, 8)?;
+///     participants.push(*participant)
+/// }
+/// ```
+pub struct AccountVec<'a, 'info, T> {
+    account: &'a AccountInfo<'info>,
+    offset: usize,
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'info, T: Pod> AccountVec<'a, 'info, T> {
+    /// Returns the space needed for a vector with a capacity of `capacity` elements.
+    pub const fn space(capacity: usize) -> usize {
+        META_LEN + capacity * size_of::<T>()
+    }
+
+    /// Creates a vector stored at `offset` in the data of `account`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is zero-sized.
+    pub fn new(account: &'a AccountInfo<'info>, offset: usize) -> Result<Self, ProgramError> {
+        assert!(size_of::<T>() > 0, "zero-sized elements are not supported");
+        check_len(account, &account.try_borrow_data()?, offset)?;
+
+        Ok(AccountVec { account, offset, marker: PhantomData })
+    }
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> Result<usize, ProgramError> {
+        self.stored_len(&self.account.try_borrow_data()?)
+    }
+
+    /// Returns `true` if the vector has no elements.
+    pub fn is_empty(&self) -> Result<bool, ProgramError> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns the number of elements the vector can hold without growing the account data.
+    pub fn capacity(&self) -> Result<usize, ProgramError> {
+        Ok(capacity(&self.account.try_borrow_data()?, self.start(), size_of::<T>()))
+    }
+
+    /// Returns the element at `index`.
+    pub fn get(&self, index: usize) -> Result<Option<T>, ProgramError> {
+        let data = self.account.try_borrow_data()?;
+
+        if index >= self.stored_len(&data)? {
+            return Ok(None);
+        }
+
+        Ok(Some(bytemuck::pod_read_unaligned(&data[self.range(index)])))
+    }
+
+    /// Borrows the elements of the vector.
+    ///
+    /// Fails with [`ProgramError::InvalidAccountData`] if the elements are not aligned for `T`.
+    pub fn as_slice(&self) -> Result<Ref<'a, [T]>, ProgramError> {
+        let data = self.account.try_borrow_data()?;
+        let elements = self.start()..self.start_of(self.stored_len(&data)?);
+
+        if bytemuck::try_cast_slice::<u8, T>(&data[elements.clone()]).is_err() {
+            return Err(self.misaligned());
+        }
+
+        Ok(Ref::map(data, |data| bytemuck::cast_slice(&data[elements])))
+    }
+
+    /// Mutably borrows the elements of the vector.
+    ///
+    /// Fails with [`ProgramError::InvalidAccountData`] if the elements are not aligned for `T`.
+    pub fn as_mut_slice(&self) -> Result<RefMut<'a, [T]>, ProgramError> {
+        let mut data = self.account.try_borrow_mut_data()?;
+        let elements = self.start()..self.start_of(self.stored_len(&data)?);
+
+        if bytemuck::try_cast_slice_mut::<u8, T>(&mut data[elements.clone()]).is_err() {
+            return Err(self.misaligned());
+        }
+
+        Ok(RefMut::map(data, |data| bytemuck::cast_slice_mut(&mut data[elements])))
+    }
+
+    /// Replaces the element at `index`.
+    pub fn set(&self, index: usize, value: T) -> ProgramResult {
+        let mut data = self.account.try_borrow_mut_data()?;
+
+        let len = self.stored_len(&data)?;
+        if index >= len {
+            return Err(self.out_of_bounds(index, len));
+        }
+
+        copy_from_slice_fast(&mut data[self.range(index)], bytemuck::bytes_of(&value));
+
+        Ok(())
+    }
+
+    /// Appends an element, growing the account data if needed.
+    pub fn push(&self, value: T) -> ProgramResult {
+        let len = self.len()?;
+        self.insert(len, value)
+    }
+
+    /// Removes the last element and returns it.
+    pub fn pop(&self) -> Result<Option<T>, ProgramError> {
+        let mut data = self.account.try_borrow_mut_data()?;
+
+        let len = self.stored_len(&data)?;
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let value = bytemuck::pod_read_unaligned(&data[self.range(len - 1)]);
+        write_u64(&mut data, self.offset, len as u64 - 1);
+
+        Ok(Some(value))
+    }
+
+    /// Inserts an element at `index`, shifting the following elements with `sol_memmove`, and
+    /// growing the account data if needed.
+    pub fn insert(&self, index: usize, value: T) -> ProgramResult {
+        let len = self.len()?;
+        if index > len {
+            return Err(self.out_of_bounds(index, len));
+        }
+
+        self.reserve(1)?;
+
+        let mut data = self.account.try_borrow_mut_data()?;
+        move_within(&mut data, self.start_of(index)..self.start_of(len), self.start_of(index + 1));
+        copy_from_slice_fast(&mut data[self.range(index)], bytemuck::bytes_of(&value));
+        write_u64(&mut data, self.offset, len as u64 + 1);
+
+        Ok(())
+    }
+
+    /// Removes the element at `index` and returns it, shifting the following elements with
+    /// `sol_memmove`.
+    pub fn remove(&self, index: usize) -> Result<T, ProgramError> {
+        let mut data = self.account.try_borrow_mut_data()?;
+
+        let len = self.stored_len(&data)?;
+        if index >= len {
+            return Err(self.out_of_bounds(index, len));
+        }
+
+        let value = bytemuck::pod_read_unaligned(&data[self.range(index)]);
+        move_within(&mut data, self.start_of(index + 1)..self.start_of(len), self.start_of(index));
+        write_u64(&mut data, self.offset, len as u64 - 1);
+
+        Ok(value)
+    }
+
+    /// Removes the element at `index` and returns it, replacing it with the last element.
+    pub fn swap_remove(&self, index: usize) -> Result<T, ProgramError> {
+        let mut data = self.account.try_borrow_mut_data()?;
+
+        let len = self.stored_len(&data)?;
+        if index >= len {
+            return Err(self.out_of_bounds(index, len));
+        }
+
+        let value = bytemuck::pod_read_unaligned(&data[self.range(index)]);
+        if index != len - 1 {
+            move_within(&mut data, self.range(len - 1), self.start_of(index));
+        }
+        write_u64(&mut data, self.offset, len as u64 - 1);
+
+        Ok(value)
+    }
+
+    /// Shortens the vector to `len` elements, if it is longer.
+    pub fn truncate(&self, len: usize) -> ProgramResult {
+        let mut data = self.account.try_borrow_mut_data()?;
+
+        if len < self.stored_len(&data)? {
+            write_u64(&mut data, self.offset, len as u64);
+        }
+
+        Ok(())
+    }
+
+    /// Removes all elements.
+    pub fn clear(&self) -> ProgramResult {
+        self.truncate(0)
+    }
+
+    /// Grows the account data, if needed, to hold at least `additional` more elements.
+    pub fn reserve(&self, additional: usize) -> ProgramResult {
+        let required = self.len()?.saturating_add(additional);
+
+        if required > self.capacity()? {
+            grow(self.account, self.start(), size_of::<T>(), required)?;
+        }
+
+        Ok(())
+    }
+
+    /// Shrinks the account data to the length of the vector.
+    pub fn shrink_to_fit(&self) -> ProgramResult {
+        let len = self.len()?;
+
+        if len < self.capacity()? {
+            crate::realloc_account_mut(self.account, self.start_of(len))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the length stored in the metadata, checked against the capacity.
+    #[inline]
+    fn stored_len(&self, data: &[u8]) -> Result<usize, ProgramError> {
+        check_len(self.account, data, self.offset)?;
+
+        let len = read_u64(data, self.offset) as usize;
+        check_stored_len(self.account, len, capacity(data, self.start(), size_of::<T>()))?;
+
+        Ok(len)
+    }
+
+    #[inline]
+    fn start(&self) -> usize {
+        self.offset + META_LEN
+    }
+
+    #[inline]
+    fn start_of(&self, index: usize) -> usize {
+        self.start() + index * size_of::<T>()
+    }
+
+    #[inline]
+    fn range(&self, index: usize) -> Range<usize> {
+        self.start_of(index)..self.start_of(index + 1)
+    }
+
+    #[cold]
+    fn out_of_bounds(&self, index: usize, len: usize) -> ProgramError {
+        log!("Error: Index {} is out of bounds of {} elements in {}", index, len, self.account.key);
+        ProgramError::InvalidArgument
+    }
+
+    #[cold]
+    fn misaligned(&self) -> ProgramError {
+        log!("Error: Account {} data is not aligned", self.account.key);
+        ProgramError::InvalidAccountData
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collections::tests::run;
+
+    #[test]
+    fn operations() {
+        let (result, data) = run(&[0; 16], |account| {
+            let vec = AccountVec::<u32>::new(account, 0)?;
+            assert_eq!(vec.capacity(), Ok(2));
+
+            for i in 0..5 {
+                vec.push(i)?;
+            }
+            vec.insert(0, 100)?;
+            vec.insert(3, 200)?;
+            vec.insert(7, 300)?;
+            assert_eq!(*vec.as_slice()?, [100, 0, 1, 200, 2, 3, 4, 300]);
+            assert_eq!(vec.capacity(), Ok(8));
+
+            assert_eq!(vec.remove(1), Ok(0));
+            assert_eq!(vec.swap_remove(0), Ok(100));
+            assert_eq!(vec.swap_remove(5), Ok(4));
+            vec.set(2, 20)?;
+            vec.as_mut_slice()?[0] += 1;
+            assert_eq!(*vec.as_slice()?, [301, 1, 20, 2, 3]);
+
+            assert_eq!(vec.pop(), Ok(Some(3)));
+            assert_eq!(vec.get(3), Ok(Some(2)));
+            assert_eq!(vec.get(4), Ok(None));
+
+            vec.shrink_to_fit()?;
+            assert_eq!(vec.capacity(), Ok(4));
+            vec.truncate(2)?;
+            assert_eq!(*vec.as_slice()?, [301, 1]);
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data.len(), AccountVec::<u32>::space(4));
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let (result, _) = run(&[0; 8], |account| {
+            let vec = AccountVec::<u32>::new(account, 0)?;
+            vec.push(1)?;
+
+            assert_eq!(vec.insert(2, 1), Err(ProgramError::InvalidArgument));
+            assert_eq!(vec.remove(1), Err(ProgramError::InvalidArgument));
+            assert_eq!(vec.swap_remove(1), Err(ProgramError::InvalidArgument));
+            assert_eq!(vec.set(1, 1), Err(ProgramError::InvalidArgument));
+            vec.clear()?;
+            assert_eq!(vec.pop(), Ok(None));
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn len_beyond_capacity() {
+        let (result, _) = run(&[0; 8], |account| {
+            let vec = AccountVec::<u32>::new(account, 0)?;
+            vec.push(1)?;
+            vec.push(2)?;
+
+            // The account data is shrunk by other code.
+            crate::realloc_account_mut(account, 12)?;
+            assert_eq!(vec.get(0), Err(ProgramError::InvalidAccountData));
+            assert_eq!(vec.pop(), Err(ProgramError::InvalidAccountData));
+            vec.push(3)
+        });
+
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        // The stored length is corrupt.
+        let data = [&u64::MAX.to_le_bytes()[..], &[0; 8]].concat();
+        let (result, _) = run(&data, |account| AccountVec::<u32>::new(account, 0)?.len().map(drop));
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn misaligned() {
+        let (result, _) = run(&[0; 9], |account| {
+            let vec = AccountVec::<u32>::new(account, 1)?;
+            vec.push(1)?;
+
+            assert_eq!(vec.get(0), Ok(Some(1)));
+            assert_eq!(vec.as_mut_slice().err(), Some(ProgramError::InvalidAccountData));
+            vec.as_slice().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+}
@@ -0,0 +1,44 @@
+/// Derives `From<E> for ProgramError` and `TryFrom<u32>` for an enum of custom program errors.
+///
+/// Each variant is mapped to [`ProgramError::Custom`] with its error code. Codes start at 0, or at
+/// the offset given with `#[error_code(offset = ..)]` on the enum, and increase by one for each
+/// variant. A variant can be given an explicit code with `#[error_code(..)]` or a discriminant,
+/// which the codes of the following variants continue from.
+///
+/// The enum must also implement [`VariantName`](crate::VariantName), which is used to log the name
+/// of the variant when it is converted into a `ProgramError`. The `TryFrom<u32>` implementation
+/// maps codes back to variants for clients, and returns the code if it does not match a variant.
+///
+/// [`ProgramError::Custom`]: solana_program::program_error::ProgramError::Custom
+///
+/// # Example
+///
+/// ```
+/// use solana_program::program_error::ProgramError;
+/// use solana_utils::VariantName;
+///
+/// #[derive(solana_utils::ProgramError, VariantName, Debug, PartialEq)]
+/// #[error_code(offset = 6000)]
+/// enum VaultError {
+///     InsufficientFunds,
+///     VaultLocked,
+///     #[error_code(7000)]
+///     Unauthorized,
+/// }
+///
+/// fn withdraw() -> Result<(), ProgramError> {
+///     // Logs "Error: VaultLocked (6001)".
+///     Err(VaultError::VaultLocked.into())
+/// }
+///
+/// assert_eq!(withdraw(), Err(ProgramError::Custom(6001)));
+/// assert_eq!(ProgramError::from(VaultError::Unauthorized), ProgramError::Custom(7000));
+/// assert_eq!(VaultError::try_from(6000), Ok(VaultError::InsufficientFunds));
+/// assert_eq!(VaultError::try_from(6002), Err(6002));
+/// ```
+pub use solana_utils_macro::ProgramError;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use solana_program::program_error::ProgramError;
+}
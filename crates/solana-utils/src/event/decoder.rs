@@ -0,0 +1,113 @@
+use std::io;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
+
+use super::{Event, DISCRIMINATOR_LEN};
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+/// Decodes an event of type `E` from data logged with `sol_log_data`.
+///
+/// Returns `None` if the data does not start with the discriminator of `E`.
+pub fn decode_event<E>(data: &[u8]) -> Option<io::Result<E>>
+where
+    E: Event + BorshDeserialize,
+{
+    let payload = data.strip_prefix(&E::DISCRIMINATOR)?;
+
+    Some(borsh::from_slice(payload))
+}
+
+/// Parses the events of type `E` emitted by `program_id` from the log messages of a transaction.
+///
+/// The log messages are expected to be in the format produced by the runtime, so that events
+/// emitted by other programs, including those invoked by `program_id`, can be excluded.
+///
+/// # Example
+///
+/// ```
+/// use borsh::{BorshDeserialize, BorshSerialize};
+/// use borsh_size::BorshSize;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::event::{parse_events, Event};
+/// use solana_utils::Discriminator;
+///
+/// #[derive(BorshSerialize, BorshDeserialize, BorshSize, Discriminator, Debug, PartialEq)]
+/// #[discriminator(bytes = b"deposit_")]
+/// struct Deposited {
+///     amount: u64,
+/// }
+///
+/// impl Event for Deposited {}
+///
+/// let program_id = Pubkey::new_unique();
+/// let logs = [
+///     format!("Program {program_id} invoke [1]"),
+///     "Program data: ZGVwb3NpdF9kAAAAAAAAAA==".to_owned(),
+///     format!("Program {program_id} success"),
+/// ];
+///
+/// let events: Vec<Deposited> = parse_events(&program_id, &logs).unwrap();
+/// assert_eq!(events, [Deposited { amount: 100 }]);
+/// ```
+pub fn parse_events<E, S>(program_id: &Pubkey, logs: &[S]) -> io::Result<Vec<E>>
+where
+    E: Event + BorshDeserialize,
+    S: AsRef<str>,
+{
+    let program_id = program_id.to_string();
+
+    let min_len = DISCRIMINATOR_LEN + E::MIN_SIZE;
+    let max_len = E::MAX_SIZE.map(|max_size| DISCRIMINATOR_LEN + max_size);
+
+    let mut events = Vec::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut data = Vec::with_capacity(max_len.unwrap_or(min_len));
+
+    for log in logs {
+        let log = log.as_ref();
+
+        if let Some(encoded) = log.strip_prefix(PROGRAM_DATA) {
+            if stack.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+
+            // Events are logged as a single base64 encoded field.
+            if encoded.contains(' ') {
+                continue;
+            }
+
+            // Skip data that cannot be an event of type `E` without decoding it.
+            let decoded_len = encoded.len() / 4 * 3;
+            if decoded_len < min_len
+                || max_len.is_some_and(|max_len| decoded_len.saturating_sub(2) > max_len)
+            {
+                continue;
+            }
+
+            data.clear();
+            if let Err(err) = STANDARD.decode_vec(encoded, &mut data) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            }
+
+            if let Some(event) = decode_event(&data) {
+                events.push(event?);
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut parts = rest.split(' ');
+
+            match (parts.next(), parts.next()) {
+                (Some(id), Some("invoke")) => stack.push(id),
+                (Some(_), Some("success" | "failed:")) => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,67 @@
+//! Structured program events logged with `sol_log_data`.
+//!
+//! An event is logged as the concatenation of its 8-byte discriminator and its borsh
+//! serialization, which is the same encoding Anchor uses for events. With the `event-decoder`
+//! feature, events can be decoded from the log messages of a transaction.
+
+use borsh::BorshSerialize;
+use borsh_size::BorshSize;
+
+use crate::Discriminator;
+
+#[cfg(feature = "event-decoder")]
+mod decoder;
+
+#[cfg(feature = "event-decoder")]
+pub use self::decoder::{decode_event, parse_events};
+
+pub use crate::DISCRIMINATOR_LEN;
+
+/// A program event that can be logged with [`emit!`](crate::emit!).
+///
+/// The [`Discriminator`] of an event identifies it in the logs. Anchor computes it in the `event`
+/// namespace.
+///
+/// # Example
+///
+/// ```
+/// use borsh::BorshSerialize;
+/// use borsh_size::BorshSize;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::emit;
+/// use solana_utils::event::Event;
+/// use solana_utils::Discriminator;
+///
+/// #[derive(BorshSerialize, BorshSize, Discriminator)]
+/// #[discriminator(namespace = "event")]
+/// struct Deposited {
+///     owner: Pubkey,
+///     amount: u64,
+/// }
+///
+/// impl Event for Deposited {}
+///
+/// emit!(Deposited { owner: Pubkey::new_unique(), amount: 100 });
+/// ```
+pub trait Event: Discriminator + BorshSerialize + BorshSize {}
+
+/// Logs `event` with `sol_log_data`.
+///
+/// The event is serialized into a buffer sized with [`BorshSize`], so it is never reallocated.
+///
+/// # Panics
+///
+/// Panics if the event fails to serialize.
+#[inline(never)]
+pub fn emit<E: Event>(event: &E) {
+    let mut data = Vec::with_capacity(DISCRIMINATOR_LEN + event.borsh_size());
+
+    data.extend_from_slice(&E::DISCRIMINATOR);
+
+    if let Err(err) = event.serialize(&mut data) {
+        crate::log!("Error: Failed to serialize event: {}", err);
+        panic!("failed to serialize event");
+    }
+
+    solana_program::log::sol_log_data(&[&data]);
+}
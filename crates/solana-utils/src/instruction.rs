@@ -0,0 +1,168 @@
+//! Parsing and dispatching of instruction enums.
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+pub use solana_utils_macro::InstructionDispatch;
+
+/// An enum of the instructions of a program, which can be parsed from instruction data and
+/// dispatched to a handler function for each variant.
+///
+/// # Derive
+///
+/// The derive macro encodes the variant as a tag at the start of the instruction data, followed by
+/// the borsh serialization of the fields of the variant. The kind of tag is selected with
+/// `#[instruction(tag = ..)]` on the enum:
+///
+/// - `u8`, the default, uses a 1-byte tag, which is the index of the variant. A variant can be
+///   given an explicit tag with `#[instruction(tag = 3)]`, which the tags of the following
+///   variants continue from.
+/// - `anchor` uses the 8-byte discriminator Anchor computes from the snake_case name of the
+///   variant, so that instructions of Anchor programs can be parsed. Acronyms are kept as one
+///   word, as in Anchor, so `SetURI` is named `set_uri`. The name can be changed with
+///   `#[instruction(name = "...")]`, or the discriminator given with
+///   `#[instruction(tag = b"...")]`.
+/// - `custom` requires each variant to specify its tag as a byte string with
+///   `#[instruction(tag = b"...")]`.
+///
+/// The fields are deserialized directly from the instruction data, so parsing does not allocate
+/// unless a field type does. Instruction data with an unknown tag, invalid fields or trailing
+/// bytes is rejected with [`ProgramError::InvalidInstructionData`].
+///
+/// [`dispatch`](InstructionDispatch::dispatch) calls the handler of the variant with the program
+/// id, the accounts and the fields of the variant, in declaration order. The handler is the
+/// function `process_<variant>` in scope, where the name of the variant is in snake_case, such as
+/// `process_set_uri`, unless another is given with `#[instruction(handler = path)]`. The enum must
+/// also implement [`VariantName`](crate::VariantName), which is used to log the variant at debug
+/// level.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::instruction::InstructionDispatch;
+/// use solana_utils::VariantName;
+///
+/// #[derive(InstructionDispatch, VariantName, Debug, PartialEq)]
+/// enum VaultInstruction {
+///     Deposit { amount: u64 },
+///     #[instruction(handler = withdraw_all)]
+///     Withdraw(u64, bool),
+///     Close,
+/// }
+///
+/// fn process_deposit(
+///     program_id: &Pubkey,
+///     accounts: &[AccountInfo],
+///     amount: u64,
+/// ) -> ProgramResult {
+///     Ok(())
+/// }
+///
+/// fn withdraw_all(
+///     program_id: &Pubkey,
+///     accounts: &[AccountInfo],
+///     amount: u64,
+///     all: bool,
+/// ) -> ProgramResult {
+///     Ok(())
+/// }
+///
+/// fn process_close(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+///     Ok(())
+/// }
+///
+/// let data = [0, 100, 0, 0, 0, 0, 0, 0, 0];
+/// let instruction = VaultInstruction::unpack(&data).unwrap();
+/// assert_eq!(instruction, VaultInstruction::Deposit { amount: 100 });
+/// assert!(VaultInstruction::unpack(&[3]).is_err());
+///
+/// VaultInstruction::process(&Pubkey::new_unique(), &[], &data).unwrap();
+/// ```
+///
+/// Instructions of an Anchor program can be parsed with `tag = anchor`:
+///
+/// ```
+/// # use solana_program::account_info::AccountInfo;
+/// # use solana_program::entrypoint::ProgramResult;
+/// # use solana_program::pubkey::Pubkey;
+/// # use solana_utils::instruction::InstructionDispatch;
+/// # use solana_utils::VariantName;
+/// #
+/// #[derive(InstructionDispatch, VariantName, Debug, PartialEq)]
+/// #[instruction(tag = anchor)]
+/// enum MetadataInstruction {
+///     SetURI { uri: String },
+/// }
+///
+/// fn process_set_uri(
+///     program_id: &Pubkey,
+///     accounts: &[AccountInfo],
+///     uri: String,
+/// ) -> ProgramResult {
+///     Ok(())
+/// }
+///
+/// // The discriminator of the `set_uri` instruction, followed by the string.
+/// let data = [72, 22, 136, 186, 78, 5, 136, 229, 1, 0, 0, 0, b'a'];
+/// let instruction = MetadataInstruction::unpack(&data).unwrap();
+/// assert_eq!(instruction, MetadataInstruction::SetURI { uri: "a".to_owned() });
+/// ```
+pub trait InstructionDispatch: Sized {
+    /// Parses an instruction from instruction data.
+    fn unpack(data: &[u8]) -> Result<Self, ProgramError>;
+
+    /// Calls the handler of the instruction.
+    fn dispatch(self, program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult;
+
+    /// Parses an instruction from instruction data and calls its handler.
+    #[inline]
+    fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        Self::unpack(data)?.dispatch(program_id, accounts)
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use borsh::BorshDeserialize;
+
+    pub use solana_program::account_info::AccountInfo;
+    pub use solana_program::entrypoint::ProgramResult;
+    pub use solana_program::program_error::ProgramError;
+    pub use solana_program::pubkey::Pubkey;
+
+    use crate::log;
+
+    #[inline(always)]
+    pub fn deserialize<T: BorshDeserialize>(
+        data: &mut &[u8],
+        variant: &'static str,
+    ) -> Result<T, ProgramError> {
+        match T::deserialize(data) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(invalid_arguments(variant)),
+        }
+    }
+
+    #[cold]
+    fn invalid_arguments(variant: &'static str) -> ProgramError {
+        log!("Error: Invalid arguments for instruction {}", variant);
+        ProgramError::InvalidInstructionData
+    }
+
+    #[cold]
+    pub fn unknown_instruction(tag: &[u8]) -> ProgramError {
+        log!("Error: Unknown instruction {}", tag);
+        ProgramError::InvalidInstructionData
+    }
+
+    #[cold]
+    pub fn trailing_data(variant: &'static str, data: &[u8]) -> ProgramError {
+        log!("Error: Instruction {} has {} trailing bytes", variant, data.len());
+        ProgramError::InvalidInstructionData
+    }
+}
@@ -1,9 +1,24 @@
 //! Internal utilities used by the crate.
 
-/// Panics if the `target_os` is not `solana`.
+/// Panics if the `target_os` is not `solana`, unless the `test-runtime` feature is enabled.
+///
+/// The accounts given after the label are accessed through the memory around them, so with the
+/// `test-runtime` feature they must also have been deserialized by the test runtime.
 macro_rules! assert_is_solana {
+    ($label:expr, $($account:expr),+ $(,)?) => {
+        assert_is_solana!($label);
+
+        #[cfg(all(not(target_os = "solana"), feature = "test-runtime"))]
+        {
+            $(
+                if !$crate::test_runtime::is_serialized($account) {
+                    panic!(concat!($label, ": account not created by the runtime"));
+                }
+            )+
+        }
+    };
     ($($label:expr)?) => {
-        if cfg!(not(target_os = "solana")) {
+        if cfg!(not(any(target_os = "solana", feature = "test-runtime"))) {
             panic!(concat!($($label, ": ",)? "not supported when target_os != \"solana\""));
         }
     };
@@ -0,0 +1,455 @@
+//! Zero-copy introspection of the instructions of the current transaction, through the
+//! instructions sysvar.
+
+use std::cell::Ref;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+use solana_program::sysvar::instructions;
+
+pub use solana_program::sysvar::instructions::BorrowedAccountMeta;
+
+use crate::{log, pubkeys_eq};
+
+const IS_SIGNER_BIT: u8 = 1 << 0;
+const IS_WRITABLE_BIT: u8 = 1 << 1;
+
+/// The length of a serialized account meta: its flags followed by its pubkey.
+const ACCOUNT_META_LEN: usize = 1 + PUBKEY_BYTES;
+
+/// Checks that the current instruction is a top-level instruction of the transaction, rather than
+/// invoked by another program.
+///
+/// Fails with [`ProgramError::InvalidArgument`] otherwise.
+#[inline]
+pub fn assert_top_level() -> ProgramResult {
+    let stack_height = get_stack_height();
+
+    if stack_height != TRANSACTION_LEVEL_STACK_HEIGHT {
+        log!("Error: Instruction must not be invoked via CPI, stack height is {}", stack_height);
+
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+/// A reader over the data of the instructions sysvar account.
+///
+/// The instructions are read from the account data when accessed, without copying them into
+/// `Instruction`s.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::ed25519_program;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::introspection::Instructions;
+/// # use solana_program::sysvar::instructions::{self, construct_instructions_data};
+/// # use solana_program::sysvar::instructions::BorrowedInstruction;
+///
+/// fn process(program_id: &Pubkey, sysvar: &AccountInfo) -> ProgramResult {
+///     let instructions = Instructions::new(sysvar)?;
+///
+///     let current = instructions.current().unwrap();
+///     assert_eq!(current.program_id(), program_id);
+///
+///     let (index, verify) = instructions.find_preceding(&ed25519_program::ID).unwrap();
+///     assert_eq!(index, 0);
+///     assert_eq!(verify.data(), [1, 2]);
+///
+///     for instruction in instructions.iter() {
+///         assert!(instruction.accounts().all(|account| !account.is_signer));
+///     }
+///
+///     Ok(())
+/// }
+/// #
+/// # let program_id = Pubkey::new_unique();
+/// # let mut data = construct_instructions_data(&[
+/// #     BorrowedInstruction { program_id: &ed25519_program::ID, accounts: vec![], data: &[1, 2] },
+/// #     BorrowedInstruction { program_id: &program_id, accounts: vec![], data: &[] },
+/// # ]);
+/// # instructions::store_current_index(&mut data, 1);
+/// # let mut lamports = 0;
+/// # let sysvar = AccountInfo::new(
+/// #     &instructions::ID, false, false, &mut lamports, &mut data, &program_id, false, 0,
+/// # );
+/// # process(&program_id, &sysvar).unwrap();
+/// ```
+pub struct Instructions<'a> {
+    data: Ref<'a, [u8]>,
+}
+
+impl<'a> Instructions<'a> {
+    /// Borrows the data of the instructions sysvar account.
+    ///
+    /// Fails with [`ProgramError::UnsupportedSysvar`] if `account` is not the instructions sysvar.
+    pub fn new(account: &'a AccountInfo) -> Result<Self, ProgramError> {
+        if !pubkeys_eq(account.key, &instructions::ID) {
+            log!("Error: Account {} is not the instructions sysvar", account.key);
+
+            return Err(ProgramError::UnsupportedSysvar);
+        }
+
+        let data = Ref::map(account.try_borrow_data()?, |data| &**data);
+
+        // The number of instructions, their offsets and the current index.
+        let len = read_u16(&data, 0).unwrap_or(0) as usize;
+        if data.len() < 2 + len * 2 + 2 {
+            log!("Error: Instructions sysvar data is too short");
+
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Instructions { data })
+    }
+
+    /// Returns the number of instructions in the transaction.
+    #[inline]
+    pub fn len(&self) -> usize {
+        read_u16(&self.data, 0).unwrap_or(0) as usize
+    }
+
+    /// Returns `true` if the transaction has no instructions.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the index of the currently executing top-level instruction.
+    #[inline]
+    pub fn current_index(&self) -> usize {
+        read_u16(&self.data, self.data.len() - 2).unwrap_or(0) as usize
+    }
+
+    /// Returns the currently executing top-level instruction.
+    #[inline]
+    pub fn current(&self) -> Option<IntrospectedInstruction<'_>> {
+        self.get(self.current_index())
+    }
+
+    /// Returns the instruction at `index`, or `None` if it is out of bounds or malformed.
+    pub fn get(&self, index: usize) -> Option<IntrospectedInstruction<'_>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        // The instructions are followed by the current index, which they must not overlap.
+        let instructions = &self.data[..self.data.len() - 2];
+
+        let mut offset = read_u16(instructions, 2 + index * 2)? as usize;
+
+        let num_accounts = read_u16(instructions, offset)? as usize;
+        offset += 2;
+        let accounts = instructions.get(offset..offset + num_accounts * ACCOUNT_META_LEN)?;
+        offset += accounts.len();
+
+        let program_id = to_pubkey(instructions.get(offset..offset + PUBKEY_BYTES)?);
+        offset += PUBKEY_BYTES;
+
+        let data_len = read_u16(instructions, offset)? as usize;
+        offset += 2;
+        let data = instructions.get(offset..offset + data_len)?;
+
+        Some(IntrospectedInstruction { program_id, accounts, data })
+    }
+
+    /// Returns an iterator over the instructions of the transaction.
+    ///
+    /// The iterator stops at the first malformed instruction.
+    pub fn iter(&self) -> impl Iterator<Item = IntrospectedInstruction<'_>> {
+        (0..self.len()).map_while(|index| self.get(index))
+    }
+
+    /// Returns the closest instruction preceding the current instruction which invokes
+    /// `program_id`, along with its index.
+    pub fn find_preceding(
+        &self,
+        program_id: &Pubkey,
+    ) -> Option<(usize, IntrospectedInstruction<'_>)> {
+        (0..self.current_index())
+            .rev()
+            .filter_map(|index| Some((index, self.get(index)?)))
+            .find(|(_, instruction)| pubkeys_eq(instruction.program_id, program_id))
+    }
+}
+
+/// An instruction read from the instructions sysvar.
+#[derive(Clone, Copy)]
+pub struct IntrospectedInstruction<'a> {
+    program_id: &'a Pubkey,
+    accounts: &'a [u8],
+    data: &'a [u8],
+}
+
+impl<'a> IntrospectedInstruction<'a> {
+    /// Returns the id of the program invoked by the instruction.
+    #[inline]
+    pub fn program_id(&self) -> &'a Pubkey {
+        self.program_id
+    }
+
+    /// Returns the instruction data.
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the number of accounts of the instruction.
+    #[inline]
+    pub fn num_accounts(&self) -> usize {
+        self.accounts.len() / ACCOUNT_META_LEN
+    }
+
+    /// Returns the account of the instruction at `index`.
+    #[inline]
+    pub fn account(&self, index: usize) -> Option<BorrowedAccountMeta<'a>> {
+        let offset = index * ACCOUNT_META_LEN;
+        self.accounts.get(offset..offset + ACCOUNT_META_LEN).map(to_account_meta)
+    }
+
+    /// Returns an iterator over the accounts of the instruction.
+    #[inline]
+    pub fn accounts(&self) -> impl Iterator<Item = BorrowedAccountMeta<'a>> {
+        self.accounts.chunks_exact(ACCOUNT_META_LEN).map(to_account_meta)
+    }
+}
+
+#[inline]
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+#[inline]
+fn to_pubkey(bytes: &[u8]) -> &Pubkey {
+    debug_assert_eq!(bytes.len(), PUBKEY_BYTES);
+    // SAFETY: `Pubkey` is a `#[repr(transparent)]` wrapper around `[u8; 32]`, so it has the same
+    // layout and an alignment of 1, and `bytes` is `PUBKEY_BYTES` long.
+    unsafe { &*(bytes.as_ptr() as *const Pubkey) }
+}
+
+#[inline]
+fn to_account_meta(bytes: &[u8]) -> BorrowedAccountMeta<'_> {
+    BorrowedAccountMeta {
+        pubkey: to_pubkey(&bytes[1..]),
+        is_signer: bytes[0] & IS_SIGNER_BIT != 0,
+        is_writable: bytes[0] & IS_WRITABLE_BIT != 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::account_info::AccountInfo;
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use solana_program::program_error::ProgramError;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::sysvar::instructions::{
+        self, construct_instructions_data, store_current_index, BorrowedInstruction,
+    };
+
+    use super::*;
+    use crate::invoke::invoke;
+    use crate::test_runtime::{process_instruction, register_program};
+
+    /// Serializes the instructions sysvar for `instructions`, with `current` executing.
+    fn sysvar_data(instructions: &[Instruction], current: u16) -> Vec<u8> {
+        let borrowed: Vec<_> = instructions
+            .iter()
+            .map(|instruction| BorrowedInstruction {
+                program_id: &instruction.program_id,
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &instruction.data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, current);
+        data
+    }
+
+    fn with_sysvar<R>(key: &Pubkey, mut data: Vec<u8>, f: impl FnOnce(&AccountInfo) -> R) -> R {
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &solana_program::sysvar::ID,
+            false,
+            0,
+        );
+        f(&account)
+    }
+
+    fn instruction(program_id: &Pubkey, data: &[u8]) -> Instruction {
+        Instruction::new_with_bytes(
+            *program_id,
+            data,
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+            ],
+        )
+    }
+
+    #[test]
+    fn reads_instructions() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let expected = [
+            instruction(&program_a, &[1, 2, 3]),
+            instruction(&program_b, &[]),
+            instruction(&program_a, &[4]),
+        ];
+
+        with_sysvar(&instructions::ID, sysvar_data(&expected, 1), |account| {
+            let instructions = Instructions::new(account).unwrap();
+
+            assert_eq!(instructions.len(), 3);
+            assert_eq!(instructions.current_index(), 1);
+            assert_eq!(instructions.current().unwrap().program_id(), &program_b);
+            assert!(instructions.get(3).is_none());
+
+            for (instruction, expected) in instructions.iter().zip(&expected) {
+                assert_eq!(instruction.program_id(), &expected.program_id);
+                assert_eq!(instruction.data(), expected.data);
+                assert_eq!(instruction.num_accounts(), expected.accounts.len());
+
+                for (i, (account, meta)) in
+                    instruction.accounts().zip(&expected.accounts).enumerate()
+                {
+                    assert_eq!(account.pubkey, &meta.pubkey);
+                    assert_eq!(account.is_signer, meta.is_signer);
+                    assert_eq!(account.is_writable, meta.is_writable);
+                    assert_eq!(instruction.account(i).unwrap().pubkey, &meta.pubkey);
+                }
+                assert!(instruction.account(expected.accounts.len()).is_none());
+            }
+            assert_eq!(instructions.iter().count(), 3);
+        });
+    }
+
+    #[test]
+    fn find_preceding() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let all = [
+            instruction(&program_a, &[0]),
+            instruction(&program_a, &[1]),
+            instruction(&program_b, &[2]),
+            instruction(&program_b, &[3]),
+            instruction(&program_a, &[4]),
+        ];
+
+        with_sysvar(&instructions::ID, sysvar_data(&all, 3), |account| {
+            let instructions = Instructions::new(account).unwrap();
+
+            // The closest preceding instruction is found, but not the current one or later ones.
+            let (index, found) = instructions.find_preceding(&program_a).unwrap();
+            assert_eq!((index, found.data()), (1, &[1][..]));
+            let (index, found) = instructions.find_preceding(&program_b).unwrap();
+            assert_eq!((index, found.data()), (2, &[2][..]));
+            assert!(instructions.find_preceding(&Pubkey::new_unique()).is_none());
+        });
+
+        with_sysvar(&instructions::ID, sysvar_data(&all, 0), |account| {
+            assert!(Instructions::new(account).unwrap().find_preceding(&program_a).is_none());
+        });
+    }
+
+    #[test]
+    fn wrong_sysvar() {
+        let data = sysvar_data(&[instruction(&Pubkey::new_unique(), &[])], 0);
+
+        with_sysvar(&Pubkey::new_unique(), data, |account| {
+            assert_eq!(Instructions::new(account).err(), Some(ProgramError::UnsupportedSysvar));
+        });
+    }
+
+    #[test]
+    fn truncated_header() {
+        for data in [vec![], vec![1], vec![2, 0, 6, 0, 0, 0]] {
+            with_sysvar(&instructions::ID, data, |account| {
+                assert_eq!(
+                    Instructions::new(account).err(),
+                    Some(ProgramError::InvalidAccountData)
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn malformed_instructions() {
+        let all =
+            [instruction(&Pubkey::new_unique(), &[1]), instruction(&Pubkey::new_unique(), &[2, 3])];
+        let valid = sysvar_data(&all, 1);
+
+        // The offset of the second instruction points past the end of the data.
+        let mut data = valid.clone();
+        data[4..6].copy_from_slice(&u16::MAX.to_le_bytes());
+        with_sysvar(&instructions::ID, data, |account| {
+            let instructions = Instructions::new(account).unwrap();
+            assert!(instructions.get(0).is_some());
+            assert!(instructions.get(1).is_none());
+            assert!(instructions.current().is_none());
+            assert_eq!(instructions.iter().count(), 1);
+        });
+
+        // The data of the last instruction overlaps the current index.
+        let mut data = valid.clone();
+        let data_len = data.len() - 2 - 2 - 2;
+        data[data_len..data_len + 2].copy_from_slice(&3u16.to_le_bytes());
+        with_sysvar(&instructions::ID, data, |account| {
+            let instructions = Instructions::new(account).unwrap();
+            assert!(instructions.get(1).is_none());
+        });
+
+        // The number of accounts is larger than the remaining data.
+        let mut data = valid;
+        let offset = u16::from_le_bytes([data[2], data[3]]) as usize;
+        data[offset..offset + 2].copy_from_slice(&1000u16.to_le_bytes());
+        with_sysvar(&instructions::ID, data, |account| {
+            let instructions = Instructions::new(account).unwrap();
+            assert!(instructions.get(0).is_none());
+            assert_eq!(instructions.iter().count(), 0);
+            assert!(instructions.find_preceding(&Pubkey::new_unique()).is_none());
+        });
+
+        // The current index is out of bounds.
+        with_sysvar(&instructions::ID, sysvar_data(&all, 2), |account| {
+            assert!(Instructions::new(account).unwrap().current().is_none());
+        });
+    }
+
+    #[test]
+    fn top_level() {
+        let program_id = Pubkey::new_unique();
+        let callee = Pubkey::new_unique();
+        register_program(callee, |_, _, _| assert_top_level());
+
+        let instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+
+        process_instruction(&instruction, &mut [], |_, _, _| assert_top_level()).unwrap();
+
+        let result = process_instruction(&instruction, &mut [], |_, accounts, _| {
+            invoke(&Instruction::new_with_bytes(callee, &[], vec![]), accounts)
+        });
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+    }
+}
@@ -0,0 +1,66 @@
+//! Diagnostic account borrow checks, enabled by the `debug-invoke` feature.
+
+use std::rc::Rc;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::AccountMeta;
+use solana_program::program_error::ProgramError;
+
+use crate::{log, pubkeys_eq};
+
+/// Checks that a writable account appearing more than once in `account_infos` shares the same
+/// `RefCell`s in every occurrence, as otherwise borrows of one occurrence would not prevent the
+/// callee from writing through another.
+///
+/// `first` is the position of the first occurrence, which is the one checked for borrows.
+pub(crate) fn check_aliasing(account_infos: &[AccountInfo], first: usize) -> ProgramResult {
+    let first_info = &account_infos[first];
+
+    for (position, account_info) in account_infos.iter().enumerate().skip(first + 1) {
+        if pubkeys_eq(first_info.key, account_info.key) && !shares_cells(first_info, account_info) {
+            log!(
+                "Error: Writable account {} appears at account infos {} and {} with inconsistent aliasing",
+                account_info.key,
+                first,
+                position,
+            );
+            return Err(ProgramError::AccountBorrowFailed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Borrows the account as requested, logging the conflicting account on failure.
+pub(crate) fn check_borrow(
+    index: usize,
+    account_meta: &AccountMeta,
+    account_info: &AccountInfo,
+) -> ProgramResult {
+    let (lamports, data) = if account_meta.is_writable {
+        (account_info.try_borrow_mut_lamports().is_ok(), account_info.try_borrow_mut_data().is_ok())
+    } else {
+        (account_info.try_borrow_lamports().is_ok(), account_info.try_borrow_data().is_ok())
+    };
+
+    let field = match (lamports, data) {
+        (true, true) => return Ok(()),
+        (false, _) => "lamports",
+        (true, false) => "data",
+    };
+
+    log!(
+        "Error: Account {} (instruction account {}) is already borrowed, cannot borrow {} as {}",
+        account_info.key,
+        index,
+        field,
+        if account_meta.is_writable { "mutable" } else { "shared" },
+    );
+
+    Err(ProgramError::AccountBorrowFailed)
+}
+
+fn shares_cells<'a>(a: &AccountInfo<'a>, b: &AccountInfo<'a>) -> bool {
+    Rc::ptr_eq(&a.lamports, &b.lamports) && Rc::ptr_eq(&a.data, &b.data)
+}
@@ -0,0 +1,53 @@
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+
+/// A borrowed cross-program instruction.
+///
+/// Unlike [`Instruction`], this does not own its accounts or data, so it can be built from
+/// slices or stack arrays without allocating.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::instruction::AccountMeta;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::invoke::InstructionRef;
+///
+/// let program_id = Pubkey::new_unique();
+/// let account = Pubkey::new_unique();
+///
+/// let accounts = [AccountMeta::new(account, false)];
+/// let data = [1, 2, 3];
+///
+/// let instruction = InstructionRef::new(&program_id, &accounts, &data);
+///
+/// assert_eq!(instruction.accounts.len(), 1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstructionRef<'a> {
+    /// Pubkey of the program that executes this instruction.
+    pub program_id: &'a Pubkey,
+    /// Metadata describing accounts that should be passed to the program.
+    pub accounts: &'a [AccountMeta],
+    /// Opaque data passed to the program for its own interpretation.
+    pub data: &'a [u8],
+}
+
+impl<'a> InstructionRef<'a> {
+    /// Creates a new borrowed instruction.
+    #[inline]
+    pub const fn new(program_id: &'a Pubkey, accounts: &'a [AccountMeta], data: &'a [u8]) -> Self {
+        Self { program_id, accounts, data }
+    }
+}
+
+impl<'a> From<&'a Instruction> for InstructionRef<'a> {
+    #[inline]
+    fn from(instruction: &'a Instruction) -> Self {
+        Self {
+            program_id: &instruction.program_id,
+            accounts: &instruction.accounts,
+            data: &instruction.data,
+        }
+    }
+}
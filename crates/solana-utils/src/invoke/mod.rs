@@ -1,12 +1,26 @@
 //! Drop-in replacements for `solana_program::program::invoke*` with improved
 //! compute unit and heap efficiency.
+//!
+//! When the `debug-invoke` feature is enabled, the checked functions log the
+//! account responsible when an account borrow check fails.
 
 use solana_program::account_info::AccountInfo;
 use solana_program::entrypoint::ProgramResult;
+#[cfg(not(feature = "debug-invoke"))]
+use solana_program::instruction::AccountMeta;
 use solana_program::instruction::Instruction;
 
+#[cfg(feature = "debug-invoke")]
+mod debug;
+mod instruction;
+mod select;
 mod stable;
 
+#[cfg(feature = "debug-invoke")]
+use self::debug::check_borrow;
+pub use self::instruction::InstructionRef;
+pub use self::select::SelectedAccountInfos;
+
 /// Invoke a cross-program instruction.
 pub fn invoke(instruction: &Instruction, account_infos: &[AccountInfo]) -> ProgramResult {
     invoke_signed(instruction, account_infos, &[])
@@ -40,23 +54,7 @@ pub fn invoke_signed(
     account_infos: &[AccountInfo],
     signers_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    // Check that the account RefCells are consistent with the request.
-    for account_meta in instruction.accounts.iter() {
-        for account_info in account_infos.iter() {
-            if account_meta.pubkey == *account_info.key {
-                if account_meta.is_writable {
-                    let _ = account_info.try_borrow_mut_lamports()?;
-                    let _ = account_info.try_borrow_mut_data()?;
-                } else {
-                    let _ = account_info.try_borrow_lamports()?;
-                    let _ = account_info.try_borrow_data()?;
-                }
-                break;
-            }
-        }
-    }
-
-    unsafe { invoke_signed_unchecked(instruction, account_infos, signers_seeds) }
+    invoke_signed_ref(&InstructionRef::from(instruction), account_infos, signers_seeds)
 }
 
 /// Invoke a cross-program instruction with signatures but don't enforce Rust's
@@ -79,12 +77,74 @@ pub unsafe fn invoke_signed_unchecked(
     instruction: &Instruction,
     account_infos: &[AccountInfo],
     signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed_ref_unchecked(&InstructionRef::from(instruction), account_infos, signers_seeds)
+}
+
+/// Invoke a borrowed cross-program instruction.
+///
+/// This function is like [`invoke`] except that it takes an [`InstructionRef`],
+/// so the instruction accounts and data do not need to be heap allocated.
+pub fn invoke_ref(instruction: &InstructionRef, account_infos: &[AccountInfo]) -> ProgramResult {
+    invoke_signed_ref(instruction, account_infos, &[])
+}
+
+/// Invoke a borrowed cross-program instruction but don't enforce Rust's
+/// aliasing rules.
+///
+/// This function is like [`invoke_unchecked`] except that it takes an
+/// [`InstructionRef`].
+///
+/// # Safety
+///
+/// If any of the writable accounts passed to the callee contain data that is
+/// borrowed within the calling program, and that data is written to by the
+/// callee, then Rust's aliasing rules will be violated and cause undefined
+/// behavior.
+pub unsafe fn invoke_ref_unchecked(
+    instruction: &InstructionRef,
+    account_infos: &[AccountInfo],
+) -> ProgramResult {
+    invoke_signed_ref_unchecked(instruction, account_infos, &[])
+}
+
+/// Invoke a borrowed cross-program instruction with program signatures.
+///
+/// This function is like [`invoke_signed`] except that it takes an
+/// [`InstructionRef`], so the instruction accounts and data do not need to be
+/// heap allocated.
+pub fn invoke_signed_ref(
+    instruction: &InstructionRef,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    check_borrows(instruction, account_infos)?;
+
+    unsafe { invoke_signed_ref_unchecked(instruction, account_infos, signers_seeds) }
+}
+
+/// Invoke a borrowed cross-program instruction with signatures but don't
+/// enforce Rust's aliasing rules.
+///
+/// This function is like [`invoke_signed_unchecked`] except that it takes an
+/// [`InstructionRef`].
+///
+/// # Safety
+///
+/// If any of the writable accounts passed to the callee contain data that is
+/// borrowed within the calling program, and that data is written to by the
+/// callee, then Rust's aliasing rules will be violated and cause undefined
+/// behavior.
+pub unsafe fn invoke_signed_ref_unchecked(
+    instruction: &InstructionRef,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
     #[cfg(target_os = "solana")]
     {
         use stable::StableInstruction;
 
-        let stable_instruction = StableInstruction::borrow(&instruction);
+        let stable_instruction = StableInstruction::borrow(instruction);
 
         let result = unsafe {
             solana_program::syscalls::sol_invoke_signed_rust(
@@ -102,10 +162,167 @@ pub unsafe fn invoke_signed_unchecked(
         }
     }
 
-    #[cfg(not(target_os = "solana"))]
+    #[cfg(all(not(target_os = "solana"), feature = "test-runtime"))]
+    {
+        crate::test_runtime::invoke_signed(instruction, account_infos, signers_seeds)
+    }
+
+    #[cfg(not(any(target_os = "solana", feature = "test-runtime")))]
     {
         core::hint::black_box((instruction, account_infos, signers_seeds));
 
         panic!("invoke_signed: not supported when target_os != \"solana\"")
     }
 }
+
+/// Checks that the account `RefCell`s are consistent with the request.
+fn check_borrows(instruction: &InstructionRef, account_infos: &[AccountInfo]) -> ProgramResult {
+    for (index, account_meta) in instruction.accounts.iter().enumerate() {
+        let position =
+            account_infos.iter().position(|account_info| account_meta.pubkey == *account_info.key);
+
+        let Some(position) = position else {
+            continue;
+        };
+
+        check_borrow(index, account_meta, &account_infos[position])?;
+
+        #[cfg(feature = "debug-invoke")]
+        if account_meta.is_writable {
+            debug::check_aliasing(account_infos, position)?;
+        }
+    }
+    Ok(())
+}
+
+/// Borrows the account as requested.
+#[cfg(not(feature = "debug-invoke"))]
+#[inline(always)]
+fn check_borrow(
+    _index: usize,
+    account_meta: &AccountMeta,
+    account_info: &AccountInfo,
+) -> ProgramResult {
+    if account_meta.is_writable {
+        let _ = account_info.try_borrow_mut_lamports()?;
+        let _ = account_info.try_borrow_mut_data()?;
+    } else {
+        let _ = account_info.try_borrow_lamports()?;
+        let _ = account_info.try_borrow_data()?;
+    }
+    Ok(())
+}
+
+/// Invoke a borrowed cross-program instruction, passing only the account infos
+/// it references.
+///
+/// The account infos referenced by the instruction are selected from
+/// `available`, which may contain every account available to the program. See
+/// [`SelectedAccountInfos`] for details.
+pub fn invoke_select<const N: usize>(
+    instruction: &InstructionRef,
+    available: &[AccountInfo],
+) -> ProgramResult {
+    invoke_signed_select::<N>(instruction, available, &[])
+}
+
+/// Invoke a borrowed cross-program instruction with program signatures,
+/// passing only the account infos it references.
+///
+/// The account infos referenced by the instruction are selected from
+/// `available`, which may contain every account available to the program. See
+/// [`SelectedAccountInfos`] for details.
+pub fn invoke_signed_select<const N: usize>(
+    instruction: &InstructionRef,
+    available: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let account_infos = SelectedAccountInfos::<N>::select(instruction.accounts, available)?;
+
+    invoke_signed_ref(instruction, &account_infos, signers_seeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::instruction::AccountMeta;
+    use solana_program::program_error::ProgramError;
+    use solana_program::pubkey::Pubkey;
+
+    use super::*;
+    use crate::test_runtime::{process_instruction, register_program, Account};
+
+    /// Runs `test` in a program with a writable and a read-only account, with an instruction
+    /// invoking a registered program with both accounts.
+    fn run(test: impl FnOnce(&Instruction, &[AccountInfo]) -> ProgramResult) {
+        let program_id = Pubkey::new_unique();
+        let callee = Pubkey::new_unique();
+        let (writable, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+        register_program(callee, |_, _, _| Ok(()));
+
+        let metas =
+            vec![AccountMeta::new(writable, false), AccountMeta::new_readonly(readonly, false)];
+        let instruction = Instruction::new_with_bytes(program_id, &[], metas.clone());
+        let cpi = Instruction::new_with_bytes(callee, &[], metas);
+
+        let mut accounts = vec![
+            (writable, Account::new(1, 0, &program_id)),
+            (readonly, Account::new(1, 0, &program_id)),
+        ];
+
+        process_instruction(&instruction, &mut accounts, |_, accounts, _| test(&cpi, accounts))
+            .unwrap();
+    }
+
+    #[test]
+    fn borrowed_accounts() {
+        run(|cpi, accounts| {
+            let [writable, readonly] = accounts else { unreachable!() };
+
+            let data = readonly.try_borrow_data()?;
+            invoke(cpi, accounts)?;
+            drop(data);
+
+            let data = writable.try_borrow_data()?;
+            assert_eq!(invoke(cpi, accounts), Err(ProgramError::AccountBorrowFailed));
+            drop(data);
+
+            let lamports = readonly.try_borrow_mut_lamports()?;
+            assert_eq!(invoke(cpi, accounts), Err(ProgramError::AccountBorrowFailed));
+            drop(lamports);
+
+            invoke(cpi, accounts)
+        });
+    }
+
+    #[test]
+    fn duplicate_account_infos() {
+        run(|cpi, accounts| {
+            let account_infos = [accounts[0].clone(), accounts[1].clone(), accounts[0].clone()];
+            invoke(cpi, &account_infos)
+        });
+    }
+
+    #[cfg(feature = "debug-invoke")]
+    #[test]
+    fn inconsistent_aliasing() {
+        run(|cpi, accounts| {
+            // Another occurrence of the writable account with its own `RefCell`s.
+            let alias = AccountInfo::new(
+                accounts[0].key,
+                false,
+                true,
+                Box::leak(Box::new(1)),
+                &mut [],
+                accounts[0].owner,
+                false,
+                0,
+            );
+
+            let account_infos = [accounts[0].clone(), accounts[1].clone(), alias];
+            assert_eq!(invoke(cpi, &account_infos), Err(ProgramError::AccountBorrowFailed));
+
+            let account_infos = [accounts[1].clone(), accounts[0].clone()];
+            invoke(cpi, &account_infos)
+        });
+    }
+}
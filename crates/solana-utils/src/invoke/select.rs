@@ -0,0 +1,210 @@
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::ptr;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::instruction::AccountMeta;
+use solana_program::program_error::ProgramError;
+
+use crate::{log, pubkeys_eq};
+
+/// The subset of the available [`AccountInfo`]s referenced by an instruction, stored on the stack.
+///
+/// At most `N` distinct accounts can be selected. Each account info occupies 48 bytes of stack
+/// space, so `N` should be kept small enough to fit within the stack frame limit of a program.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::system_instruction;
+/// use solana_utils::invoke::{invoke, SelectedAccountInfos};
+///
+/// fn transfer(accounts: &[AccountInfo], lamports: u64) -> ProgramResult {
+///     let instruction = system_instruction::transfer(accounts[0].key, accounts[1].key, lamports);
+///
+///     // Only the account infos referenced by the instruction are passed to the callee.
+///     let account_infos = SelectedAccountInfos::<2>::select(&instruction.accounts, accounts)?;
+///
+///     invoke(&instruction, &account_infos)
+/// }
+/// ```
+pub struct SelectedAccountInfos<'a, 'b, const N: usize> {
+    infos: [MaybeUninit<AccountInfo<'a>>; N],
+    len: usize,
+    _marker: PhantomData<&'b [AccountInfo<'a>]>,
+}
+
+impl<'a, 'b, const N: usize> SelectedAccountInfos<'a, 'b, N> {
+    /// Selects the account infos referenced by `accounts` from `available`, in the order they are
+    /// first referenced.
+    ///
+    /// Returns [`ProgramError::NotEnoughAccountKeys`] if a referenced account is not available,
+    /// or [`ProgramError::InvalidArgument`] if more than `N` distinct accounts are referenced.
+    pub fn select(
+        accounts: &[AccountMeta],
+        available: &'b [AccountInfo<'a>],
+    ) -> Result<Self, ProgramError> {
+        // SAFETY: An array of `MaybeUninit` does not require initialization.
+        let infos = unsafe { MaybeUninit::<[MaybeUninit<AccountInfo>; N]>::uninit().assume_init() };
+
+        let mut selected = Self { infos, len: 0, _marker: PhantomData };
+
+        'metas: for account_meta in accounts {
+            // Skip accounts that have already been selected.
+            for account_info in selected.iter() {
+                if pubkeys_eq(account_info.key, &account_meta.pubkey) {
+                    continue 'metas;
+                }
+            }
+
+            let account_info = match available
+                .iter()
+                .find(|account_info| pubkeys_eq(account_info.key, &account_meta.pubkey))
+            {
+                Some(account_info) => account_info,
+                None => {
+                    log!(
+                        "Error: Missing account {} required by the instruction",
+                        account_meta.pubkey
+                    );
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                }
+            };
+
+            if selected.len == N {
+                log!("Error: Instruction references more than {} accounts", N);
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            // SAFETY: The copy is never dropped, so the reference counts of the shared fields are
+            // unaffected, and it cannot outlive the original as `available` is borrowed for `'b`.
+            selected.infos[selected.len].write(unsafe { ptr::read(account_info) });
+            selected.len += 1;
+        }
+
+        Ok(selected)
+    }
+}
+
+impl<'a, const N: usize> Deref for SelectedAccountInfos<'a, '_, N> {
+    type Target = [AccountInfo<'a>];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: The first `len` elements are initialized.
+        unsafe { std::slice::from_raw_parts(self.infos.as_ptr().cast(), self.len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::account_info::AccountInfo;
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use solana_program::program_error::ProgramError;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::{system_instruction, system_program};
+
+    use super::SelectedAccountInfos;
+    use crate::invoke::{invoke_select, InstructionRef};
+    use crate::test_runtime::{process_instruction, Account};
+
+    fn keys(n: usize) -> Vec<Pubkey> {
+        (0..n).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    fn run(
+        keys: &[Pubkey],
+        test: impl FnOnce(&[AccountInfo]) -> Result<(), ProgramError>,
+    ) -> Result<(), ProgramError> {
+        let metas = keys.iter().map(|key| AccountMeta::new(*key, true)).collect();
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], metas);
+        let mut accounts: Vec<_> =
+            keys.iter().map(|key| (*key, Account::new(1_000, 0, &system_program::ID))).collect();
+
+        process_instruction(&instruction, &mut accounts, |_, account_infos, _| test(account_infos))
+    }
+
+    #[test]
+    fn selects_referenced_accounts_in_order() {
+        let keys = keys(4);
+        let metas = [
+            AccountMeta::new(keys[2], false),
+            AccountMeta::new_readonly(keys[0], false),
+            AccountMeta::new(keys[2], true),
+        ];
+
+        run(&keys, |available| {
+            let selected = SelectedAccountInfos::<2>::select(&metas, available)?;
+
+            assert_eq!(selected.len(), 2);
+            assert_eq!(*selected[0].key, keys[2]);
+            assert_eq!(*selected[1].key, keys[0]);
+            assert!(selected[0].is_writable);
+
+            // The selected infos share the lamports and data of the originals.
+            **selected[0].try_borrow_mut_lamports()? = 7;
+            assert_eq!(available[2].lamports(), 7);
+            let _data = selected[0].try_borrow_mut_data()?;
+            assert!(available[2].try_borrow_data().is_err());
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn selects_nothing_for_no_accounts() {
+        run(&keys(1), |available| {
+            assert!(SelectedAccountInfos::<0>::select(&[], available)?.is_empty());
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn missing_account() {
+        let keys = keys(2);
+        let metas =
+            [AccountMeta::new(keys[0], false), AccountMeta::new(Pubkey::new_unique(), false)];
+
+        let result = run(&keys, |available| {
+            SelectedAccountInfos::<4>::select(&metas, available).map(|_| ())
+        });
+        assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+    }
+
+    #[test]
+    fn too_many_accounts() {
+        let keys = keys(3);
+        let metas: Vec<_> = keys.iter().map(|key| AccountMeta::new(*key, false)).collect();
+
+        let result = run(&keys, |available| {
+            SelectedAccountInfos::<2>::select(&metas, available).map(|_| ())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+
+        // Duplicates do not count towards the limit.
+        let metas = [metas[0].clone(), metas[1].clone(), metas[0].clone()];
+        run(&keys, |available| SelectedAccountInfos::<2>::select(&metas, available).map(|_| ()))
+            .unwrap();
+    }
+
+    #[test]
+    fn invoke_with_selected_accounts() {
+        let keys = keys(3);
+        let transfer = system_instruction::transfer(&keys[2], &keys[0], 100);
+        let mut all_keys = keys.clone();
+        all_keys.push(system_program::ID);
+
+        run(&all_keys, |available| {
+            invoke_select::<3>(&InstructionRef::from(&transfer), available)?;
+
+            assert_eq!(available[0].lamports(), 1_100);
+            assert_eq!(available[2].lamports(), 900);
+            Ok(())
+        })
+        .unwrap();
+    }
+}
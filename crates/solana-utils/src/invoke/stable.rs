@@ -3,9 +3,11 @@
 use std::marker::PhantomData;
 use std::ptr::NonNull;
 
-use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::instruction::AccountMeta;
 use solana_program::pubkey::Pubkey;
 
+use super::InstructionRef;
+
 #[repr(C)]
 pub(crate) struct StableInstruction<'a> {
     accounts: StableVec<'a, AccountMeta>,
@@ -22,25 +24,25 @@ pub(crate) struct StableVec<'a, T> {
 }
 
 impl<'a> StableInstruction<'a> {
-    pub(crate) fn borrow(instruction: &'a Instruction) -> Self {
+    pub(crate) fn borrow(instruction: &InstructionRef<'a>) -> Self {
         Self {
-            accounts: StableVec::borrow(&instruction.accounts),
-            data: StableVec::borrow(&instruction.data),
-            program_id: instruction.program_id,
+            accounts: StableVec::borrow(instruction.accounts),
+            data: StableVec::borrow(instruction.data),
+            program_id: *instruction.program_id,
         }
     }
 }
 
 impl<'a, T> StableVec<'a, T> {
-    pub(crate) fn borrow(vec: &'a Vec<T>) -> Self {
-        let ptr = vec.as_ptr();
-        let cap = vec.capacity();
-        let len = vec.len();
+    pub(crate) fn borrow(slice: &'a [T]) -> Self {
+        let ptr = slice.as_ptr();
+        let len = slice.len();
 
-        // SAFETY: `Vec` guarantees that `ptr` is non-null.
+        // SAFETY: Slices guarantee that `ptr` is non-null.
         let ptr = unsafe { NonNull::new_unchecked(ptr.cast_mut()) };
 
-        Self { ptr, cap, len, _marker: PhantomData }
+        // The runtime only reads `len` elements, so the slice length doubles as the capacity.
+        Self { ptr, cap: len, len, _marker: PhantomData }
     }
 }
 
@@ -2,13 +2,31 @@
 mod internal;
 
 mod account;
+mod error;
+mod loader;
 mod macros;
 mod misc;
+mod return_data;
 mod traits;
 
+pub mod accounts;
+pub mod collections;
+pub mod event;
+pub mod instruction;
+pub mod introspection;
 pub mod invoke;
+pub mod log;
+pub mod pda;
+pub mod profile;
+pub mod sigverify;
 pub mod syscalls;
 
+#[cfg(all(feature = "test-runtime", not(target_os = "solana")))]
+pub mod test_runtime;
+
 pub use account::*;
+pub use error::*;
+pub use loader::*;
 pub use misc::*;
+pub use return_data::*;
 pub use traits::*;
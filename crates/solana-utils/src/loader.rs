@@ -0,0 +1,361 @@
+use std::cell::{Ref, RefMut};
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of};
+
+use bytemuck::Pod;
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::{log, pubkeys_eq, Discriminator, DISCRIMINATOR_LEN};
+
+/// A loader of `#[repr(C)]` [`Pod`] account types, which are accessed directly in the account
+/// data rather than deserialized.
+///
+/// The account data starts with the [`Discriminator`] of `T`, followed by `T`, and may be longer.
+/// Each load checks that the account is owned by the program, and that its data is large enough
+/// and starts with the discriminator of `T`. As account data is 8-byte aligned, `T` must not have
+/// an alignment greater than 8.
+///
+/// # Example
+///
+/// ```
+/// use bytemuck::{Pod, Zeroable};
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::{create_or_allocate_account, AccountLoader, Discriminator};
+///
+/// #[derive(Clone, Copy, Pod, Zeroable, Discriminator)]
+/// #[repr(C)]
+/// struct Counter {
+///     authority: Pubkey,
+///     count: u64,
+/// }
+///
+/// fn initialize<'a>(
+///     program_id: &Pubkey,
+///     counter: &AccountInfo<'a>,
+///     authority: &AccountInfo<'a>,
+///     system_program: &AccountInfo<'a>,
+/// ) -> ProgramResult {
+///     let space = AccountLoader::<Counter>::SPACE;
+///     create_or_allocate_account(counter, authority, system_program, space, program_id, &[])?;
+///
+///     let loader = AccountLoader::<Counter>::new(counter, program_id);
+///     let mut counter = loader.load_init()?;
+///     counter.authority = *authority.key;
+///
+///     Ok(())
+/// }
+///
+/// fn increment(program_id: &Pubkey, counter: &AccountInfo) -> ProgramResult {
+///     let loader = AccountLoader::<Counter>::new(counter, program_id);
+///     loader.load_mut()?.count += 1;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct AccountLoader<'a, 'info, T> {
+    account: &'a AccountInfo<'info>,
+    program_id: &'a Pubkey,
+    marker: PhantomData<T>,
+}
+
+impl<'a, 'info, T: Pod + Discriminator> AccountLoader<'a, 'info, T> {
+    /// The minimum length of the account data: the discriminator followed by `T`.
+    pub const SPACE: usize = DISCRIMINATOR_LEN + size_of::<T>();
+
+    /// Creates a loader of `account`, which must be owned by `program_id`.
+    pub fn new(account: &'a AccountInfo<'info>, program_id: &'a Pubkey) -> Self {
+        AccountLoader { account, program_id, marker: PhantomData }
+    }
+
+    /// Returns the loaded account.
+    pub fn account(&self) -> &'a AccountInfo<'info> {
+        self.account
+    }
+
+    /// Borrows the account data as `T`.
+    pub fn load(&self) -> Result<Ref<'a, T>, ProgramError> {
+        self.check_owner()?;
+
+        let data = self.account.try_borrow_data()?;
+        self.check_data(&data)?;
+        self.check_discriminator(&data)?;
+
+        Ok(Ref::map(data, |data| bytemuck::from_bytes(&data[DISCRIMINATOR_LEN..Self::SPACE])))
+    }
+
+    /// Mutably borrows the account data as `T`. The account must be writable.
+    pub fn load_mut(&self) -> Result<RefMut<'a, T>, ProgramError> {
+        self.check_owner()?;
+        self.check_writable()?;
+
+        let data = self.account.try_borrow_mut_data()?;
+        self.check_data(&data)?;
+        self.check_discriminator(&data)?;
+
+        Ok(RefMut::map(data, |data| {
+            bytemuck::from_bytes_mut(&mut data[DISCRIMINATOR_LEN..Self::SPACE])
+        }))
+    }
+
+    /// Initializes the account by writing the discriminator of `T`, and mutably borrows the
+    /// account data as `T`. The account must be writable.
+    ///
+    /// The account must not have been initialized, that is its discriminator must be zero, as it
+    /// is for an account created with [`create_or_allocate_account`]. `T` is not zeroed, as the
+    /// data of a created account already is.
+    ///
+    /// [`create_or_allocate_account`]: crate::create_or_allocate_account
+    pub fn load_init(&self) -> Result<RefMut<'a, T>, ProgramError> {
+        self.check_owner()?;
+        self.check_writable()?;
+
+        let mut data = self.account.try_borrow_mut_data()?;
+        self.check_data(&data)?;
+
+        let discriminator = &mut data[..DISCRIMINATOR_LEN];
+        if discriminator.iter().any(|byte| *byte != 0) {
+            log!("Error: Account {} is already initialized", self.account.key);
+
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        crate::copy_from_slice_fast(discriminator, &T::DISCRIMINATOR);
+
+        Ok(RefMut::map(data, |data| {
+            bytemuck::from_bytes_mut(&mut data[DISCRIMINATOR_LEN..Self::SPACE])
+        }))
+    }
+
+    fn check_owner(&self) -> Result<(), ProgramError> {
+        if !pubkeys_eq(self.account.owner, self.program_id) {
+            log!(
+                "Error: Account {} is owned by {} instead of {}",
+                self.account.key,
+                self.account.owner,
+                self.program_id,
+            );
+
+            return Err(ProgramError::IllegalOwner);
+        }
+
+        Ok(())
+    }
+
+    fn check_writable(&self) -> Result<(), ProgramError> {
+        if !self.account.is_writable {
+            log!("Error: Account {} is not writable", self.account.key);
+
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Ok(())
+    }
+
+    fn check_data(&self, data: &[u8]) -> Result<(), ProgramError> {
+        if data.len() < Self::SPACE {
+            log!(
+                "Error: Account {} has {} bytes of data, expected at least {}",
+                self.account.key,
+                data.len(),
+                Self::SPACE,
+            );
+
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        if (data.as_ptr() as usize + DISCRIMINATOR_LEN) % align_of::<T>() != 0 {
+            log!("Error: Account {} data is not aligned", self.account.key);
+
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+
+    fn check_discriminator(&self, data: &[u8]) -> Result<(), ProgramError> {
+        if !T::check_discriminator(data) {
+            log!("Error: Account {} has an invalid discriminator", self.account.key);
+
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::{Pod, Zeroable};
+    use solana_program::entrypoint::ProgramResult;
+    use solana_program::instruction::{AccountMeta, Instruction};
+
+    use super::*;
+    use crate::test_runtime::{process_instruction, Account};
+
+    #[derive(Clone, Copy, Pod, Zeroable)]
+    #[repr(C)]
+    struct Counter {
+        count: u64,
+    }
+
+    impl Discriminator for Counter {
+        const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN] = *b"counter!";
+    }
+
+    type Loader<'a, 'info> = AccountLoader<'a, 'info, Counter>;
+
+    /// Processes an instruction with a writable and a read-only account holding `data`.
+    fn run(
+        owner: Option<Pubkey>,
+        data: &[u8],
+        test: impl FnOnce(&Pubkey, &AccountInfo, &AccountInfo) -> ProgramResult,
+    ) -> (ProgramResult, Vec<u8>) {
+        let program_id = Pubkey::new_unique();
+        let (writable, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new(writable, false), AccountMeta::new_readonly(readonly, false)],
+        );
+
+        let mut account = Account::new(1_000_000, 0, &owner.unwrap_or(program_id));
+        account.data = data.to_vec();
+        let mut accounts = vec![(writable, account.clone()), (readonly, account)];
+
+        let result = process_instruction(&instruction, &mut accounts, |program_id, accounts, _| {
+            test(program_id, &accounts[0], &accounts[1])
+        });
+
+        (result, accounts.swap_remove(0).1.data)
+    }
+
+    fn counter_data(count: u64) -> Vec<u8> {
+        [&Counter::DISCRIMINATOR[..], &count.to_le_bytes()].concat()
+    }
+
+    #[test]
+    fn load() {
+        let (result, data) = run(None, &counter_data(41), |program_id, writable, readonly| {
+            assert_eq!(Loader::new(readonly, program_id).load()?.count, 41);
+
+            Loader::new(writable, program_id).load_mut()?.count += 1;
+            assert_eq!(Loader::new(writable, program_id).load()?.count, 42);
+
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data, counter_data(42));
+    }
+
+    #[test]
+    fn load_longer_data() {
+        let mut data = counter_data(7);
+        data.extend_from_slice(&[1; 16]);
+
+        let (result, _) = run(None, &data, |program_id, _, readonly| {
+            assert_eq!(Loader::new(readonly, program_id).load()?.count, 7);
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn load_init() {
+        let (result, data) = run(None, &[0; Loader::SPACE], |program_id, writable, _| {
+            Loader::new(writable, program_id).load_init()?.count = 3;
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(data, counter_data(3));
+    }
+
+    #[test]
+    fn load_init_initialized() {
+        let (result, _) = run(None, &counter_data(0), |program_id, writable, _| {
+            Loader::new(writable, program_id).load_init().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn illegal_owner() {
+        let (result, _) = run(Some(Pubkey::new_unique()), &counter_data(0), |program_id, w, r| {
+            assert_eq!(Loader::new(r, program_id).load().err(), Some(ProgramError::IllegalOwner));
+            assert_eq!(
+                Loader::new(w, program_id).load_mut().err(),
+                Some(ProgramError::IllegalOwner),
+            );
+            Loader::new(w, program_id).load_init().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+    }
+
+    #[test]
+    fn not_writable() {
+        let (result, _) = run(None, &counter_data(0), |program_id, _, readonly| {
+            assert_eq!(
+                Loader::new(readonly, program_id).load_init().err(),
+                Some(ProgramError::InvalidArgument),
+            );
+            Loader::new(readonly, program_id).load_mut().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+    }
+
+    #[test]
+    fn data_too_small() {
+        let data = &counter_data(0)[..Loader::SPACE - 1];
+        let (result, _) = run(None, data, |program_id, writable, readonly| {
+            assert_eq!(
+                Loader::new(readonly, program_id).load().err(),
+                Some(ProgramError::AccountDataTooSmall),
+            );
+            assert_eq!(
+                Loader::new(writable, program_id).load_mut().err(),
+                Some(ProgramError::AccountDataTooSmall),
+            );
+            Loader::new(writable, program_id).load_init().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::AccountDataTooSmall));
+    }
+
+    #[test]
+    fn invalid_discriminator() {
+        let mut data = counter_data(0);
+        data[0] ^= 1;
+
+        let (result, _) = run(None, &data, |program_id, writable, readonly| {
+            assert_eq!(
+                Loader::new(readonly, program_id).load().err(),
+                Some(ProgramError::InvalidAccountData),
+            );
+            Loader::new(writable, program_id).load_mut().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn borrowed() {
+        let (result, _) = run(None, &counter_data(0), |program_id, writable, _| {
+            let loader = Loader::new(writable, program_id);
+            let _counter = loader.load()?;
+
+            assert_eq!(loader.load().map(|counter| counter.count), Ok(0));
+            loader.load_mut().map(drop)
+        });
+
+        assert_eq!(result, Err(ProgramError::AccountBorrowFailed));
+    }
+}
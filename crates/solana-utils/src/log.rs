@@ -0,0 +1,570 @@
+//! Allocation-free formatting for the [`log!`](crate::log!) macro.
+//!
+//! Messages are formatted into a [`LogBuffer`] on the stack. Arguments formatted with a plain
+//! `{}` placeholder are written with their [`Loggable`] implementation if they have one, which
+//! avoids the overhead of `core::fmt`, and with their `Display` implementation otherwise.
+//!
+//! The leveled macros, such as [`log_debug!`](crate::log_debug!), only log if their [`Level`] is
+//! enabled by [`MAX_LEVEL`]. Disabled levels are compiled out, including the evaluation of their
+//! arguments.
+
+use std::fmt;
+
+use solana_program::pubkey::Pubkey;
+
+/// The capacity of a [`LogBuffer`] in bytes.
+pub const LOG_BUFFER_LEN: usize = 256;
+
+/// The marker that replaces the end of a message that does not fit in a [`LogBuffer`].
+pub const TRUNCATION_MARKER: &str = "...";
+
+/// A fixed-size buffer a log message is formatted into.
+pub struct LogBuffer {
+    bytes: [u8; LOG_BUFFER_LEN],
+    len: usize,
+    truncated: bool,
+}
+
+impl LogBuffer {
+    /// Creates an empty buffer.
+    #[inline]
+    pub const fn new() -> LogBuffer {
+        LogBuffer { bytes: [0; LOG_BUFFER_LEN], len: 0, truncated: false }
+    }
+
+    /// Appends `s`, truncating it at a character boundary if it does not fit.
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        let available = LOG_BUFFER_LEN - self.len;
+
+        let len = if s.len() <= available {
+            s.len()
+        } else {
+            self.truncated = true;
+            floor_char_boundary(s.as_bytes(), available)
+        };
+
+        self.bytes[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+    }
+
+    /// Appends ASCII `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if `bytes` are not ASCII.
+    #[inline]
+    pub fn push_ascii(&mut self, bytes: &[u8]) {
+        debug_assert!(bytes.is_ascii());
+
+        let len = bytes.len().min(LOG_BUFFER_LEN - self.len);
+        if len < bytes.len() {
+            self.truncated = true;
+        }
+
+        self.bytes[self.len..self.len + len].copy_from_slice(&bytes[..len]);
+        self.len += len;
+    }
+
+    /// Returns the message, replacing its end with [`TRUNCATION_MARKER`] if it was truncated.
+    pub fn finish(&mut self) -> &str {
+        if self.truncated {
+            let end = floor_char_boundary(
+                &self.bytes[..self.len],
+                LOG_BUFFER_LEN - TRUNCATION_MARKER.len(),
+            );
+
+            self.bytes[end..end + TRUNCATION_MARKER.len()]
+                .copy_from_slice(TRUNCATION_MARKER.as_bytes());
+            self.len = end + TRUNCATION_MARKER.len();
+            self.truncated = false;
+        }
+
+        // SAFETY: Only whole UTF-8 characters are written to the buffer.
+        unsafe { std::str::from_utf8_unchecked(&self.bytes[..self.len]) }
+    }
+
+    /// Logs the message.
+    #[inline]
+    pub fn log(&mut self) {
+        solana_program::log::sol_log(self.finish());
+    }
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        LogBuffer::new()
+    }
+}
+
+impl fmt::Write for LogBuffer {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+/// Returns the largest index of at most `index` that is a character boundary of `bytes`.
+fn floor_char_boundary(bytes: &[u8], index: usize) -> usize {
+    if index >= bytes.len() {
+        return bytes.len();
+    }
+
+    let mut index = index;
+    // UTF-8 continuation bytes have the form 0b10xxxxxx.
+    while index > 0 && bytes[index] & 0xc0 == 0x80 {
+        index -= 1;
+    }
+    index
+}
+
+/// Values that can be written to a [`LogBuffer`] without `core::fmt`.
+///
+/// Integers are written in decimal, [`Pubkey`]s in base58, and byte slices and arrays in
+/// lowercase hex. Other than byte slices, which do not implement `Display`, the output matches the
+/// `Display` implementation of the type.
+pub trait Loggable {
+    /// Writes the value to `buf`.
+    fn write_log(&self, buf: &mut LogBuffer);
+
+    /// Logs the value on its own.
+    ///
+    /// This can be overridden where a dedicated syscall is cheaper than formatting.
+    #[inline]
+    fn log(&self) {
+        let mut buf = LogBuffer::new();
+        self.write_log(&mut buf);
+        buf.log();
+    }
+}
+
+impl<T: Loggable + ?Sized> Loggable for &T {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        (**self).write_log(buf)
+    }
+
+    #[inline]
+    fn log(&self) {
+        (**self).log()
+    }
+}
+
+impl<T: Loggable + ?Sized> Loggable for &mut T {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        (**self).write_log(buf)
+    }
+
+    #[inline]
+    fn log(&self) {
+        (**self).log()
+    }
+}
+
+impl Loggable for str {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        buf.push_str(self)
+    }
+
+    #[inline]
+    fn log(&self) {
+        solana_program::log::sol_log(self)
+    }
+}
+
+impl Loggable for String {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        buf.push_str(self)
+    }
+
+    #[inline]
+    fn log(&self) {
+        solana_program::log::sol_log(self)
+    }
+}
+
+impl Loggable for bool {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        buf.push_str(if *self { "true" } else { "false" })
+    }
+}
+
+impl Loggable for char {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        buf.push_str(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+macro_rules! impl_loggable_unsigned {
+    ($($ty:ty => $via:ty),* $(,)?) => {$(
+        impl Loggable for $ty {
+            #[inline]
+            fn write_log(&self, buf: &mut LogBuffer) {
+                write_decimal(buf, *self as $via, false)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_loggable_signed {
+    ($($ty:ty => $via:ty),* $(,)?) => {$(
+        impl Loggable for $ty {
+            #[inline]
+            fn write_log(&self, buf: &mut LogBuffer) {
+                write_decimal(buf, self.unsigned_abs() as $via, *self < 0)
+            }
+        }
+    )*};
+}
+
+impl_loggable_unsigned!(u8 => u64, u16 => u64, u32 => u64, u64 => u64, usize => u64, u128 => u128);
+impl_loggable_signed!(i8 => u64, i16 => u64, i32 => u64, i64 => u64, isize => u64, i128 => u128);
+
+trait Decimal: Copy {
+    fn is_zero(self) -> bool;
+    /// Divides by 10 in place, returning the remainder.
+    fn div_rem_10(&mut self) -> u8;
+}
+
+impl Decimal for u64 {
+    #[inline]
+    fn is_zero(self) -> bool {
+        self == 0
+    }
+
+    #[inline]
+    fn div_rem_10(&mut self) -> u8 {
+        let rem = (*self % 10) as u8;
+        *self /= 10;
+        rem
+    }
+}
+
+impl Decimal for u128 {
+    #[inline]
+    fn is_zero(self) -> bool {
+        self == 0
+    }
+
+    #[inline]
+    fn div_rem_10(&mut self) -> u8 {
+        let rem = (*self % 10) as u8;
+        *self /= 10;
+        rem
+    }
+}
+
+fn write_decimal<T: Decimal>(buf: &mut LogBuffer, mut value: T, negative: bool) {
+    // Enough for `u128::MAX` and a sign.
+    let mut digits = [0; 40];
+    let mut start = digits.len();
+
+    loop {
+        start -= 1;
+        digits[start] = b'0' + value.div_rem_10();
+
+        if value.is_zero() {
+            break;
+        }
+    }
+
+    if negative {
+        start -= 1;
+        digits[start] = b'-';
+    }
+
+    buf.push_ascii(&digits[start..]);
+}
+
+impl Loggable for Pubkey {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        let mut encoded = [0; 44];
+        let len = encode_base58(&self.to_bytes(), &mut encoded);
+
+        buf.push_ascii(&encoded[..len]);
+    }
+
+    /// Logs the pubkey with `sol_log_pubkey`.
+    #[inline]
+    fn log(&self) {
+        Pubkey::log(self)
+    }
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes `input` in base58, returning the length of the encoding written to `output`.
+fn encode_base58(input: &[u8; 32], output: &mut [u8; 44]) -> usize {
+    // Little-endian base58 digits of the input.
+    let mut digits = [0u8; 44];
+    let mut len = 0;
+
+    for &byte in input {
+        let mut carry = byte as u32;
+
+        for digit in &mut digits[..len] {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+
+        while carry > 0 {
+            digits[len] = (carry % 58) as u8;
+            len += 1;
+            carry /= 58;
+        }
+    }
+
+    // Leading zero bytes are encoded as leading ones.
+    let zeros = input.iter().take_while(|&&byte| byte == 0).count();
+
+    output[..zeros].fill(BASE58_ALPHABET[0]);
+
+    for (out, &digit) in output[zeros..].iter_mut().zip(digits[..len].iter().rev()) {
+        *out = BASE58_ALPHABET[digit as usize];
+    }
+
+    zeros + len
+}
+
+impl Loggable for [u8] {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+
+        for &byte in self {
+            buf.push_ascii(&[HEX[(byte >> 4) as usize], HEX[(byte & 0xf) as usize]]);
+        }
+    }
+}
+
+impl<const N: usize> Loggable for [u8; N] {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        self[..].write_log(buf)
+    }
+}
+
+impl Loggable for Vec<u8> {
+    #[inline]
+    fn write_log(&self, buf: &mut LogBuffer) {
+        self[..].write_log(buf)
+    }
+}
+
+/// Logs `value` with a single `sol_log_64_`, which is cheaper than formatting it.
+///
+/// The syscall logs its four other arguments as well, so this logs `0x0, 0x0, 0x0, 0x0, ` followed
+/// by `value` in hex. Use [`log!`](crate::log!) to log the value in decimal.
+#[inline]
+pub fn log_u64(value: u64) {
+    solana_program::log::sol_log_64(0, 0, 0, 0, value)
+}
+
+/// The level of a log message, from most to least severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(usize)]
+pub enum Level {
+    /// Logged with [`log_error!`](crate::log_error!).
+    Error = 1,
+    /// Logged with [`log_warn!`](crate::log_warn!).
+    Warn,
+    /// Logged with [`log_info!`](crate::log_info!).
+    Info,
+    /// Logged with [`log_debug!`](crate::log_debug!).
+    Debug,
+    /// Logged with [`log_trace!`](crate::log_trace!).
+    Trace,
+}
+
+impl Level {
+    /// Returns whether messages of this level are enabled by [`MAX_LEVEL`].
+    #[inline]
+    pub const fn enabled(self) -> bool {
+        self as usize <= MAX_LEVEL as usize
+    }
+}
+
+/// The maximum level of log messages that are enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(usize)]
+pub enum LevelFilter {
+    /// All leveled log messages are disabled.
+    Off,
+    /// Only [`Level::Error`] messages are enabled.
+    Error,
+    /// [`Level::Warn`] messages and above are enabled.
+    Warn,
+    /// [`Level::Info`] messages and above are enabled.
+    Info,
+    /// [`Level::Debug`] messages and above are enabled.
+    Debug,
+    /// All leveled log messages are enabled.
+    Trace,
+}
+
+/// The maximum level of log messages that are enabled, selected at compile time by the
+/// `max-level-*` features.
+///
+/// If more than one of the features is enabled, the most restrictive one applies. Without any of
+/// them, all levels are enabled.
+pub const MAX_LEVEL: LevelFilter = if cfg!(feature = "max-level-off") {
+    LevelFilter::Off
+} else if cfg!(feature = "max-level-error") {
+    LevelFilter::Error
+} else if cfg!(feature = "max-level-warn") {
+    LevelFilter::Warn
+} else if cfg!(feature = "max-level-info") {
+    LevelFilter::Info
+} else if cfg!(feature = "max-level-debug") {
+    LevelFilter::Debug
+} else {
+    LevelFilter::Trace
+};
+
+#[doc(hidden)]
+pub mod __private {
+    use std::fmt::{self, Write};
+
+    pub use solana_program::log::sol_log;
+    pub use solana_utils_macro::format_log;
+
+    use super::{LogBuffer, Loggable};
+
+    /// Formats a message into a [`LogBuffer`] and logs it.
+    ///
+    /// This is never inlined, so that the buffer does not occupy the stack frame of the caller.
+    #[inline(never)]
+    pub fn log_with<F: FnOnce(&mut LogBuffer)>(f: F) {
+        let mut buf = LogBuffer::new();
+        f(&mut buf);
+        buf.log();
+    }
+
+    /// Wraps a `log!` argument, so that [`Loggable`] is preferred over `Display` when writing it.
+    pub struct Wrap<'a, T: ?Sized>(pub &'a T);
+
+    pub trait ViaLoggable {
+        fn __write_log(&self, buf: &mut LogBuffer);
+        fn __log(&self);
+    }
+
+    impl<T: Loggable + ?Sized> ViaLoggable for Wrap<'_, T> {
+        #[inline]
+        fn __write_log(&self, buf: &mut LogBuffer) {
+            self.0.write_log(buf)
+        }
+
+        #[inline]
+        fn __log(&self) {
+            self.0.log()
+        }
+    }
+
+    pub trait ViaDisplay {
+        fn __write_log(&self, buf: &mut LogBuffer);
+        fn __log(&self);
+    }
+
+    impl<T: fmt::Display + ?Sized> ViaDisplay for &Wrap<'_, T> {
+        #[inline]
+        fn __write_log(&self, buf: &mut LogBuffer) {
+            let _ = write!(buf, "{}", self.0);
+        }
+
+        #[inline]
+        fn __log(&self) {
+            log_with(|buf| ViaDisplay::__write_log(self, buf))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written<T: Loggable + ?Sized>(value: &T) -> String {
+        let mut buf = LogBuffer::new();
+        value.write_log(&mut buf);
+        buf.finish().to_owned()
+    }
+
+    #[test]
+    fn truncation() {
+        let mut buf = LogBuffer::new();
+        buf.push_str(&"a".repeat(LOG_BUFFER_LEN));
+        assert_eq!(buf.finish(), "a".repeat(LOG_BUFFER_LEN));
+
+        buf.push_str("a");
+        let expected = "a".repeat(LOG_BUFFER_LEN - TRUNCATION_MARKER.len()) + TRUNCATION_MARKER;
+        assert_eq!(buf.finish(), expected);
+
+        let mut buf = LogBuffer::new();
+        buf.push_str(&"a".repeat(LOG_BUFFER_LEN - 2));
+        buf.push_ascii(b"abc");
+        assert_eq!(buf.finish(), expected);
+    }
+
+    #[test]
+    fn truncation_at_char_boundary() {
+        // A 3-byte character straddling the end of the buffer is dropped entirely.
+        let mut buf = LogBuffer::new();
+        buf.push_str(&"a".repeat(LOG_BUFFER_LEN - 1));
+        buf.push_str("€");
+        let expected = "a".repeat(LOG_BUFFER_LEN - TRUNCATION_MARKER.len()) + TRUNCATION_MARKER;
+        assert_eq!(buf.finish(), expected);
+
+        // A character straddling the start of the marker is replaced entirely.
+        let mut buf = LogBuffer::new();
+        let prefix = "a".repeat(LOG_BUFFER_LEN - TRUNCATION_MARKER.len() - 1);
+        buf.push_str(&prefix);
+        buf.push_str(&"€".repeat(10));
+        assert_eq!(buf.finish(), prefix + TRUNCATION_MARKER);
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(written(&0u8), "0");
+        assert_eq!(written(&42u64), "42");
+        assert_eq!(written(&u128::MAX), u128::MAX.to_string());
+        assert_eq!(written(&-1i8), "-1");
+        assert_eq!(written(&i64::MIN), i64::MIN.to_string());
+        assert_eq!(written(&i128::MIN), i128::MIN.to_string());
+    }
+
+    #[test]
+    fn base58() {
+        assert_eq!(written(&Pubkey::default()), "11111111111111111111111111111111");
+
+        let mut leading_zeros = [0xff; 32];
+        leading_zeros[..2].fill(0);
+        let keys = [
+            Pubkey::new_from_array(leading_zeros),
+            Pubkey::new_from_array([0xff; 32]),
+            solana_program::system_program::ID,
+            solana_program::sysvar::rent::ID,
+            Pubkey::new_unique(),
+        ];
+        for key in keys {
+            assert_eq!(written(&key), key.to_string());
+        }
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(written(&[0u8; 0]), "");
+        assert_eq!(written(&[0xde, 0xad, 0xbe, 0xef]), "deadbeef");
+        assert_eq!(written(&vec![0x00, 0x0f, 0xf0]), "000ff0");
+
+        let truncated = written(&[0xab; LOG_BUFFER_LEN][..]);
+        assert_eq!(truncated.len(), LOG_BUFFER_LEN);
+        assert!(truncated.ends_with(TRUNCATION_MARKER));
+    }
+}
@@ -1,12 +1,214 @@
 /// Prints a message to the log.
+///
+/// The message is formatted into a [`LogBuffer`](crate::log::LogBuffer) on the stack rather than
+/// a heap-allocated `String`, and is truncated if it does not fit. Arguments formatted with a
+/// plain `{}` placeholder are written with their [`Loggable`](crate::log::Loggable)
+/// implementation when they have one, so integers, pubkeys and byte slices are formatted without
+/// the overhead of `core::fmt`. A message consisting of a single pubkey is logged with
+/// `sol_log_pubkey`. To log a single `u64` with the cheaper `sol_log_64_`, which logs it in hex,
+/// use [`log_u64`](crate::log::log_u64).
+///
+/// The format string can also be a macro such as `concat!`, in which case the message is
+/// formatted with `core::fmt`.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::log;
+///
+/// let key = Pubkey::new_unique();
+/// let data = [0xde, 0xad, 0xbe, 0xef];
+///
+/// log!("Account {} has {} lamports", key, 1_000_000u64);
+/// log!("Data: {}", data); // Logs "Data: deadbeef".
+/// log!("Account: {key:?}"); // Other formatting falls back to `core::fmt`.
+/// log!("{}", key); // Logged with `sol_log_pubkey`.
+/// log!("{}", 42u64); // Logs "42".
+///
+/// macro_rules! prefixed {
+///     ($msg:literal) => {
+///         concat!("Vault: ", $msg)
+///     };
+/// }
+/// log!(prefixed!("{} has {} lamports"), key, 1_000_000u64);
+/// log!(concat!("Account ", "{}"), key);
+/// ```
 #[macro_export]
 macro_rules! log {
     ($($args:tt)*) => {
-        match ::core::format_args!($($args)*) {
-            args => match args.as_str() {
-                Some(msg) => ::solana_program::log::sol_log(msg),
-                None => ::solana_program::log::sol_log(&args.to_string()),
-            }
+        $crate::log::__private::format_log!($crate, $($args)*)
+    };
+}
+
+/// Prints a message to the log at the given [`Level`](crate::log::Level), if it is enabled by
+/// [`MAX_LEVEL`](crate::log::MAX_LEVEL).
+///
+/// If the level is disabled, the message is compiled out and its arguments are not evaluated.
+/// The message is formatted as by [`log!`].
+#[macro_export]
+macro_rules! log_level {
+    ($level:expr, $($args:tt)*) => {
+        if {
+            const ENABLED: bool = $crate::log::Level::enabled($level);
+            ENABLED
+        } {
+            $crate::log!($($args)*);
+        }
+    };
+}
+
+/// Prints a message to the log at [`Level::Error`](crate::log::Level::Error).
+///
+/// See [`log_level!`] for details.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::{log_debug, log_error};
+///
+/// let key = Pubkey::new_unique();
+///
+/// log_error!("Error: Account {} is not writable", key);
+///
+/// // Compiled out, including the call to `expensive`, if the debug level is disabled.
+/// # fn expensive() -> u64 { 0 }
+/// log_debug!("Value: {}", expensive());
+/// ```
+#[macro_export]
+macro_rules! log_error {
+    ($($args:tt)*) => {
+        $crate::log_level!($crate::log::Level::Error, $($args)*)
+    };
+}
+
+/// Prints a message to the log at [`Level::Warn`](crate::log::Level::Warn).
+///
+/// See [`log_level!`] for details.
+#[macro_export]
+macro_rules! log_warn {
+    ($($args:tt)*) => {
+        $crate::log_level!($crate::log::Level::Warn, $($args)*)
+    };
+}
+
+/// Prints a message to the log at [`Level::Info`](crate::log::Level::Info).
+///
+/// See [`log_level!`] for details.
+#[macro_export]
+macro_rules! log_info {
+    ($($args:tt)*) => {
+        $crate::log_level!($crate::log::Level::Info, $($args)*)
+    };
+}
+
+/// Prints a message to the log at [`Level::Debug`](crate::log::Level::Debug).
+///
+/// See [`log_level!`] for details.
+#[macro_export]
+macro_rules! log_debug {
+    ($($args:tt)*) => {
+        $crate::log_level!($crate::log::Level::Debug, $($args)*)
+    };
+}
+
+/// Prints a message to the log at [`Level::Trace`](crate::log::Level::Trace).
+///
+/// See [`log_level!`] for details.
+#[macro_export]
+macro_rules! log_trace {
+    ($($args:tt)*) => {
+        $crate::log_level!($crate::log::Level::Trace, $($args)*)
+    };
+}
+
+/// Logs an [`Event`](crate::event::Event) with `sol_log_data`.
+///
+/// See [`emit`](crate::event::emit) for details.
+#[macro_export]
+macro_rules! emit {
+    ($event:expr $(,)?) => {
+        $crate::event::emit(&$event)
+    };
+}
+
+/// Logs the compute units consumed until the end of the enclosing block under `label`.
+///
+/// Expands to a [`CuScope`](crate::profile::CuScope) guard if the `profile` feature is enabled,
+/// and to nothing otherwise.
+///
+/// # Example
+///
+/// ```
+/// use solana_utils::cu_scope;
+///
+/// fn process() {
+///     cu_scope!("process");
+///     {
+///         cu_scope!("process/deserialize");
+///         // ...
+///     }
+///     // ...
+/// }
+/// # process();
+/// ```
+#[cfg(feature = "profile")]
+#[macro_export]
+macro_rules! cu_scope {
+    ($label:expr $(,)?) => {
+        let __cu_scope = $crate::profile::CuScope::new($label);
+    };
+}
+
+/// Logs the compute units consumed until the end of the enclosing block under `label`.
+///
+/// Expands to a [`CuScope`](crate::profile::CuScope) guard if the `profile` feature is enabled,
+/// and to nothing otherwise.
+#[cfg(not(feature = "profile"))]
+#[macro_export]
+macro_rules! cu_scope {
+    ($label:expr $(,)?) => {};
+}
+
+/// Logs the remaining compute units with `sol_log_compute_units_`, optionally preceded by a
+/// message formatted as by [`log!`].
+///
+/// Compiled out, including the message, unless the `profile` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use solana_utils::log_compute_units;
+///
+/// log_compute_units!();
+/// log_compute_units!("After transfer {}", 1);
+/// ```
+#[cfg(feature = "profile")]
+#[macro_export]
+macro_rules! log_compute_units {
+    () => {
+        $crate::profile::__private::sol_log_compute_units()
+    };
+    ($($args:tt)+) => {{
+        $crate::log!($($args)+);
+        $crate::profile::__private::sol_log_compute_units();
+    }};
+}
+
+/// Logs the remaining compute units with `sol_log_compute_units_`, optionally preceded by a
+/// message formatted as by [`log!`].
+///
+/// Compiled out, including the message, unless the `profile` feature is enabled.
+#[cfg(not(feature = "profile"))]
+#[macro_export]
+macro_rules! log_compute_units {
+    () => {
+        ()
+    };
+    ($($args:tt)+) => {
+        if false {
+            $crate::log!($($args)+);
         }
     };
 }
@@ -19,3 +19,73 @@ pub fn pubkeys_eq(a: &Pubkey, b: &Pubkey) -> bool {
         *a == *b
     }
 }
+
+/// Checks two byte slices for equality in a computationally cheap way using `sol_memcmp`.
+///
+/// Slices of different lengths are never equal.
+#[inline]
+pub fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        // SAFETY: `a` and `b` are both valid for reads of `a.len()` bytes.
+        unsafe { crate::syscalls::memcmp(a.as_ptr(), b.as_ptr(), a.len()) == 0 }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        a == b
+    }
+}
+
+/// Copies all bytes from `src` into `dst` using `sol_memcpy`.
+///
+/// # Panics
+///
+/// Panics if the two slices have different lengths, as [`<[u8]>::copy_from_slice`] does.
+///
+/// [`<[u8]>::copy_from_slice`]: slice::copy_from_slice
+#[inline]
+#[track_caller]
+pub fn copy_from_slice_fast(dst: &mut [u8], src: &[u8]) {
+    if dst.len() != src.len() {
+        len_mismatch_fail(dst.len(), src.len());
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        // SAFETY: `dst` and `src` are valid for `dst.len()` bytes, and cannot overlap as `dst` is
+        // borrowed mutably.
+        unsafe { crate::syscalls::memcpy(dst.as_mut_ptr(), src.as_ptr(), dst.len()) }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        dst.copy_from_slice(src)
+    }
+}
+
+/// Sets all bytes of `dst` to `val` using `sol_memset`.
+#[inline]
+pub fn fill_fast(dst: &mut [u8], val: u8) {
+    #[cfg(target_os = "solana")]
+    {
+        // SAFETY: `dst` is valid for writes of `dst.len()` bytes.
+        unsafe { crate::syscalls::memset(dst.as_mut_ptr(), val, dst.len()) }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        dst.fill(val)
+    }
+}
+
+#[cold]
+#[track_caller]
+#[inline(never)]
+fn len_mismatch_fail(dst_len: usize, src_len: usize) -> ! {
+    panic!(
+        "source slice length ({}) does not match destination slice length ({})",
+        src_len, dst_len,
+    );
+}
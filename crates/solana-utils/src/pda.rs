@@ -0,0 +1,462 @@
+//! Program-derived address helpers that do not allocate.
+
+use solana_program::account_info::AccountInfo;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+
+pub use solana_utils_macro::Pda;
+
+use crate::{log, pubkeys_eq};
+
+/// Every possible bump seed, so that bump seeds can be borrowed for `'static`.
+static BUMPS: [u8; 256] = {
+    let mut bumps = [0; 256];
+    let mut i = 0;
+    while i < bumps.len() {
+        bumps[i] = i as u8;
+        i += 1;
+    }
+    bumps
+};
+
+/// The seeds of a program-derived address, together with its bump seed.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::pda::Seeds;
+///
+/// let program_id = Pubkey::new_unique();
+/// let authority = Pubkey::new_unique();
+///
+/// let (address, seeds) = Seeds::find(&[b"vault", authority.as_ref()], &program_id, None).unwrap();
+///
+/// assert_eq!(seeds.create_program_address(&program_id).unwrap(), address);
+/// assert!(seeds.verify(&address, &program_id).is_ok());
+///
+/// // Pass `&seeds.signers()` to `invoke_signed` to sign for `address`.
+/// assert_eq!(seeds.signers()[0].len(), 3);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Seeds<'a> {
+    // The seeds, followed by the bump seed.
+    seeds: [&'a [u8]; MAX_SEEDS],
+    len: usize,
+}
+
+impl<'a> Seeds<'a> {
+    /// Creates seeds from `seeds` and a known `bump` seed.
+    ///
+    /// Returns [`ProgramError::MaxSeedLengthExceeded`] if there are too many seeds, or if any
+    /// seed is too long.
+    pub fn new(seeds: &[&'a [u8]], bump: u8) -> Result<Seeds<'a>, ProgramError> {
+        check_seeds(seeds, MAX_SEEDS - 1)?;
+
+        let mut new = Seeds { seeds: [&[]; MAX_SEEDS], len: seeds.len() };
+        new.seeds[..seeds.len()].copy_from_slice(seeds);
+
+        new.set_bump(bump);
+
+        Ok(new)
+    }
+
+    /// Finds the program-derived address for `seeds` and the canonical bump seed.
+    ///
+    /// If `bump` is `Some`, the address is created with the given bump seed instead of searching
+    /// for the canonical bump seed, which is considerably cheaper. The caller is responsible for
+    /// the given bump seed being canonical, for example by only using bump seeds previously
+    /// returned by this function.
+    pub fn find(
+        seeds: &[&'a [u8]],
+        program_id: &Pubkey,
+        bump: Option<u8>,
+    ) -> Result<(Pubkey, Seeds<'a>), ProgramError> {
+        let mut new = Seeds::new(seeds, bump.unwrap_or(u8::MAX))?;
+
+        if bump.is_some() {
+            let address = new.create_program_address(program_id)?;
+            return Ok((address, new));
+        }
+
+        let (address, bump) = find_address(new.seeds(), program_id)?;
+        new.set_bump(bump);
+
+        Ok((address, new))
+    }
+
+    /// Returns the bump seed.
+    #[inline]
+    pub fn bump(&self) -> u8 {
+        self.seeds[self.len][0]
+    }
+
+    /// Returns the seeds, without the bump seed.
+    #[inline]
+    pub fn seeds(&self) -> &[&'a [u8]] {
+        &self.seeds[..self.len]
+    }
+
+    /// Returns the seeds followed by the bump seed, as expected when signing for the address.
+    #[inline]
+    pub fn signer_seeds(&self) -> &[&'a [u8]] {
+        &self.seeds[..=self.len]
+    }
+
+    /// Returns the signer seeds in the shape expected by
+    /// [`invoke_signed`](crate::invoke::invoke_signed) and
+    /// [`create_or_allocate_account`](crate::create_or_allocate_account).
+    #[inline]
+    pub fn signers(&self) -> [&[&'a [u8]]; 1] {
+        [self.signer_seeds()]
+    }
+
+    /// Creates the program-derived address for these seeds.
+    #[inline]
+    pub fn create_program_address(&self, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        create_address(self.signer_seeds(), program_id)
+    }
+
+    /// Verifies that `address` is the program-derived address for these seeds.
+    ///
+    /// Returns [`ProgramError::InvalidSeeds`] if it is not.
+    pub fn verify(&self, address: &Pubkey, program_id: &Pubkey) -> Result<(), ProgramError> {
+        let expected = self.create_program_address(program_id)?;
+
+        if !pubkeys_eq(address, &expected) {
+            log!("Error: Address {} does not match derived address {}", address, expected);
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(())
+    }
+
+    fn set_bump(&mut self, bump: u8) {
+        let bump = bump as usize;
+        self.seeds[self.len] = &BUMPS[bump..bump + 1];
+    }
+}
+
+/// Seeds of a program-derived address stored inline, for seeds that are not borrowed from
+/// elsewhere, such as integers converted to bytes.
+#[derive(Clone, Debug)]
+pub struct OwnedSeeds {
+    bytes: [[u8; MAX_SEED_LEN]; MAX_SEEDS - 1],
+    lens: [u8; MAX_SEEDS - 1],
+    len: usize,
+    bump: u8,
+}
+
+impl OwnedSeeds {
+    /// Creates empty seeds, with a bump seed of `u8::MAX`.
+    pub const fn new() -> OwnedSeeds {
+        OwnedSeeds {
+            bytes: [[0; MAX_SEED_LEN]; MAX_SEEDS - 1],
+            lens: [0; MAX_SEEDS - 1],
+            len: 0,
+            bump: u8::MAX,
+        }
+    }
+
+    /// Appends a seed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the seed is longer than [`MAX_SEED_LEN`], or if there is no room left for it
+    /// alongside the bump seed.
+    pub fn push(&mut self, seed: &[u8]) {
+        assert!(self.len < MAX_SEEDS - 1, "too many seeds");
+        assert!(seed.len() <= MAX_SEED_LEN, "seed exceeds the maximum seed length");
+
+        self.bytes[self.len][..seed.len()].copy_from_slice(seed);
+        self.lens[self.len] = seed.len() as u8;
+        self.len += 1;
+    }
+
+    /// Returns the bump seed.
+    #[inline]
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    /// Sets the bump seed.
+    #[inline]
+    pub fn set_bump(&mut self, bump: u8) {
+        self.bump = bump;
+    }
+
+    /// Borrows the seeds.
+    pub fn seeds(&self) -> Seeds<'_> {
+        let mut seeds = Seeds { seeds: [&[]; MAX_SEEDS], len: self.len };
+
+        for (i, (bytes, len)) in self.bytes.iter().zip(self.lens).take(self.len).enumerate() {
+            seeds.seeds[i] = &bytes[..len as usize];
+        }
+
+        seeds.set_bump(self.bump);
+        seeds
+    }
+}
+
+impl Default for OwnedSeeds {
+    fn default() -> Self {
+        OwnedSeeds::new()
+    }
+}
+
+/// A program-derived address schema, usually implemented with `#[derive(Pda)]`.
+///
+/// Each field of the struct is a seed, in declaration order. Fields can be a `Pubkey`, a
+/// primitive integer or a `[u8; N]` byte array. Integers are little-endian unless the field is
+/// marked with `#[pda(be)]`. Constant seeds are added with `#[pda(prefix = b"...")]`, either on
+/// the struct to precede every field, or on a field to precede that field.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::pda::Pda;
+///
+/// #[derive(Pda)]
+/// #[pda(prefix = b"order")]
+/// struct OrderAddress {
+///     market: Pubkey,
+///     #[pda(prefix = b"id", be)]
+///     id: u64,
+/// }
+///
+/// let program_id = Pubkey::new_unique();
+/// let order = OrderAddress { market: Pubkey::new_unique(), id: 7 };
+///
+/// let (address, bump) = order.find(&program_id).unwrap();
+///
+/// let expected = Pubkey::create_program_address(
+///     &[b"order", order.market.as_ref(), b"id", &7u64.to_be_bytes(), &[bump]],
+///     &program_id,
+/// );
+/// assert_eq!(expected.unwrap(), address);
+///
+/// // Pass `&seeds.seeds().signers()` to `invoke_signed` to sign for `address`.
+/// let seeds = order.signer_seeds(bump);
+/// assert_eq!(seeds.seeds().create_program_address(&program_id).unwrap(), address);
+/// ```
+///
+/// The length of a byte array seed can depend on a const generic parameter. It is checked when
+/// the seeds are written, and fails to compile if it is longer than 32 bytes.
+///
+/// ```
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::pda::Pda;
+///
+/// #[derive(Pda)]
+/// struct NamedAddress<const N: usize> {
+///     name: [u8; N],
+/// }
+///
+/// let program_id = Pubkey::new_unique();
+/// let (address, _) = NamedAddress { name: *b"alice" }.find(&program_id).unwrap();
+///
+/// let (expected, _) = Pubkey::find_program_address(&[b"alice"], &program_id);
+/// assert_eq!(expected, address);
+/// ```
+///
+/// ```compile_fail
+/// # use solana_program::pubkey::Pubkey;
+/// # use solana_utils::pda::Pda;
+/// #
+/// # #[derive(Pda)]
+/// # struct NamedAddress<const N: usize> {
+/// #     name: [u8; N],
+/// # }
+/// #
+/// NamedAddress { name: [0; 33] }.find(&Pubkey::new_unique());
+/// ```
+pub trait Pda {
+    /// Appends the seeds of the address, excluding the bump seed.
+    fn write_seeds(&self, seeds: &mut OwnedSeeds);
+
+    /// Finds the address and its canonical bump seed.
+    fn find(&self, program_id: &Pubkey) -> Result<(Pubkey, u8), ProgramError> {
+        let mut seeds = OwnedSeeds::new();
+        self.write_seeds(&mut seeds);
+
+        find_address(seeds.seeds().seeds(), program_id)
+    }
+
+    /// Returns the seeds of the address, including the given bump seed.
+    fn signer_seeds(&self, bump: u8) -> OwnedSeeds {
+        let mut seeds = OwnedSeeds::new();
+        self.write_seeds(&mut seeds);
+        seeds.set_bump(bump);
+        seeds
+    }
+
+    /// Verifies that `account` is at the address, returning the bump seed.
+    ///
+    /// If `bump` is `None`, the canonical bump seed is searched for. Otherwise the given bump
+    /// seed is used, which is considerably cheaper, and the caller is responsible for it being
+    /// canonical.
+    ///
+    /// Returns [`ProgramError::InvalidSeeds`] if the account is not at the address.
+    fn verify(
+        &self,
+        account: &AccountInfo,
+        program_id: &Pubkey,
+        bump: Option<u8>,
+    ) -> Result<u8, ProgramError> {
+        let (address, bump) = match bump {
+            Some(bump) => {
+                (self.signer_seeds(bump).seeds().create_program_address(program_id)?, bump)
+            }
+            None => self.find(program_id)?,
+        };
+
+        if !pubkeys_eq(account.key, &address) {
+            log!("Error: Account {} does not match derived address {}", account.key, address);
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(bump)
+    }
+}
+
+/// Creates a program-derived address from `seeds`, which should include the bump seed.
+///
+/// Returns [`ProgramError::InvalidSeeds`] if the seeds produce an address that lies on the
+/// ed25519 curve, or [`ProgramError::MaxSeedLengthExceeded`] if there are too many seeds or any
+/// seed is too long. The lengths are checked before the syscall, which would otherwise abort the
+/// program.
+#[inline]
+pub fn create_program_address(
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    check_seeds(seeds, MAX_SEEDS)?;
+    create_address(seeds, program_id)
+}
+
+/// Creates a program-derived address from seeds whose lengths have been checked.
+#[inline]
+fn create_address(seeds: &[&[u8]], program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+    #[cfg(target_os = "solana")]
+    {
+        let mut bytes = [0; 32];
+
+        let result = unsafe {
+            solana_program::syscalls::sol_create_program_address(
+                seeds as *const _ as *const u8,
+                seeds.len() as u64,
+                program_id as *const _ as *const u8,
+                bytes.as_mut_ptr(),
+            )
+        };
+
+        match result {
+            solana_program::entrypoint::SUCCESS => Ok(Pubkey::from(bytes)),
+            _ => Err(ProgramError::InvalidSeeds),
+        }
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        Ok(Pubkey::create_program_address(seeds, program_id)?)
+    }
+}
+
+/// Finds a valid program-derived address from `seeds`, which should not include the bump seed,
+/// and the canonical bump seed.
+///
+/// Returns [`ProgramError::InvalidSeeds`] if no bump seed produces a valid address, or
+/// [`ProgramError::MaxSeedLengthExceeded`] if there are too many seeds to add the bump seed or any
+/// seed is too long. The lengths are checked before the syscall, which would otherwise abort the
+/// program.
+#[inline]
+pub fn find_program_address(
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+) -> Result<(Pubkey, u8), ProgramError> {
+    check_seeds(seeds, MAX_SEEDS - 1)?;
+    find_address(seeds, program_id)
+}
+
+/// Finds a program-derived address from seeds whose lengths have been checked.
+#[inline]
+fn find_address(seeds: &[&[u8]], program_id: &Pubkey) -> Result<(Pubkey, u8), ProgramError> {
+    #[cfg(target_os = "solana")]
+    {
+        let mut bytes = [0; 32];
+        let mut bump = u8::MAX;
+
+        let result = unsafe {
+            solana_program::syscalls::sol_try_find_program_address(
+                seeds as *const _ as *const u8,
+                seeds.len() as u64,
+                program_id as *const _ as *const u8,
+                bytes.as_mut_ptr(),
+                &mut bump as *mut u8,
+            )
+        };
+
+        match result {
+            solana_program::entrypoint::SUCCESS => Ok((Pubkey::from(bytes), bump)),
+            _ => Err(ProgramError::InvalidSeeds),
+        }
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        Pubkey::try_find_program_address(seeds, program_id).ok_or(ProgramError::InvalidSeeds)
+    }
+}
+
+/// Checks that there are at most `max_seeds` seeds, each at most [`MAX_SEED_LEN`] bytes long.
+fn check_seeds(seeds: &[&[u8]], max_seeds: usize) -> Result<(), ProgramError> {
+    if seeds.len() > max_seeds {
+        log!("Error: Expected at most {} seeds, got {}", max_seeds, seeds.len());
+        return Err(ProgramError::MaxSeedLengthExceeded);
+    }
+
+    for (i, seed) in seeds.iter().enumerate() {
+        if seed.len() > MAX_SEED_LEN {
+            log!("Error: Seed {} exceeds the maximum seed length of {}", i, MAX_SEED_LEN);
+            return Err(ProgramError::MaxSeedLengthExceeded);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_lengths() {
+        let program_id = Pubkey::new_unique();
+        let long = [0; MAX_SEED_LEN + 1];
+        let seeds = [&[0u8][..]; MAX_SEEDS];
+
+        assert_eq!(
+            create_program_address(&[&long], &program_id),
+            Err(ProgramError::MaxSeedLengthExceeded),
+        );
+        assert_eq!(
+            create_program_address(&[&[0u8][..]; MAX_SEEDS + 1], &program_id),
+            Err(ProgramError::MaxSeedLengthExceeded),
+        );
+        assert_eq!(
+            find_program_address(&[&long], &program_id),
+            Err(ProgramError::MaxSeedLengthExceeded),
+        );
+        assert_eq!(
+            find_program_address(&seeds, &program_id),
+            Err(ProgramError::MaxSeedLengthExceeded),
+        );
+
+        let (address, bump) = find_program_address(&seeds[1..], &program_id).unwrap();
+        let bump = [bump];
+        let mut signer_seeds = seeds;
+        signer_seeds[MAX_SEEDS - 1] = &bump;
+        assert_eq!(create_program_address(&signer_seeds, &program_id), Ok(address));
+    }
+}
@@ -0,0 +1,55 @@
+//! Compute unit profiling.
+//!
+//! [`cu_scope!`](crate::cu_scope!) and [`log_compute_units!`](crate::log_compute_units!) are
+//! compiled out unless the `profile` feature is enabled, so they can be left in program code.
+//! Off-chain, the logs of profiled transactions can be aggregated into a [`Report`].
+
+#[cfg(not(target_os = "solana"))]
+mod report;
+
+#[cfg(not(target_os = "solana"))]
+pub use self::report::{Report, ScopeStats};
+
+use solana_program::compute_units::sol_remaining_compute_units;
+
+/// The prefix of the messages logged by [`CuScope`].
+pub const SCOPE_LOG_PREFIX: &str = "CU ";
+
+/// A guard which logs the compute units consumed between its creation and its drop.
+///
+/// The message has the form `CU <label>: <units>`, starting with [`SCOPE_LOG_PREFIX`]. The count
+/// includes the cost of the `sol_remaining_compute_units` syscall made on creation, and nested
+/// scopes are included in the count of the enclosing scope.
+///
+/// This is usually created with [`cu_scope!`](crate::cu_scope!).
+#[must_use = "the compute units are logged when the scope is dropped"]
+pub struct CuScope {
+    label: &'static str,
+    start: u64,
+}
+
+impl CuScope {
+    /// Starts a scope named `label`.
+    #[inline(always)]
+    pub fn new(label: &'static str) -> Self {
+        CuScope { label, start: sol_remaining_compute_units() }
+    }
+
+    /// Returns the label of the scope.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+}
+
+impl Drop for CuScope {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let consumed = self.start.saturating_sub(sol_remaining_compute_units());
+        crate::log!("{}{}: {}", SCOPE_LOG_PREFIX, self.label, consumed);
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use solana_program::log::sol_log_compute_units;
+}
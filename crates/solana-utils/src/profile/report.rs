@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::SCOPE_LOG_PREFIX;
+
+const PROGRAM_LOG: &str = "Program log: ";
+
+/// The compute units consumed by all executions of a [`CuScope`](super::CuScope) with the same
+/// label.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScopeStats {
+    /// The number of executions of the scope.
+    pub count: u64,
+    /// The total compute units consumed.
+    pub total: u64,
+    /// The minimum compute units consumed by one execution.
+    pub min: u64,
+    /// The maximum compute units consumed by one execution.
+    pub max: u64,
+}
+
+impl ScopeStats {
+    /// Returns the mean compute units consumed by one execution.
+    pub fn mean(&self) -> u64 {
+        self.total.checked_div(self.count).unwrap_or(0)
+    }
+
+    fn add(&mut self, units: u64) {
+        if self.count == 0 {
+            self.min = units;
+            self.max = units;
+        } else {
+            self.min = self.min.min(units);
+            self.max = self.max.max(units);
+        }
+        self.count += 1;
+        self.total += units;
+    }
+}
+
+/// A per-label report of the compute units logged by [`CuScope`](super::CuScope).
+///
+/// The [`Display`](fmt::Display) implementation formats the report as a table.
+///
+/// # Example
+///
+/// ```
+/// use solana_utils::profile::Report;
+///
+/// let logs = [
+///     "Program log: CU deserialize: 1200",
+///     "Program log: CU transfer: 3400",
+///     "Program log: CU deserialize: 1000",
+/// ];
+///
+/// let mut report = Report::new();
+/// report.add_logs(&logs);
+///
+/// let stats = report.get("deserialize").unwrap();
+/// assert_eq!((stats.count, stats.total, stats.mean()), (2, 2200, 1100));
+/// println!("{report}");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    scopes: BTreeMap<String, ScopeStats>,
+}
+
+impl Report {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    /// Adds the scopes logged in the log messages of a transaction.
+    ///
+    /// Log messages which were not logged by a scope are ignored, so the logs can be added to the
+    /// report unfiltered.
+    pub fn add_logs<S: AsRef<str>>(&mut self, logs: &[S]) {
+        for log in logs {
+            if let Some((label, units)) = parse_scope_log(log.as_ref()) {
+                self.add(label, units);
+            }
+        }
+    }
+
+    /// Adds one execution of the scope `label` which consumed `units`.
+    pub fn add(&mut self, label: &str, units: u64) {
+        match self.scopes.get_mut(label) {
+            Some(stats) => stats.add(units),
+            None => self.scopes.entry(label.to_owned()).or_default().add(units),
+        }
+    }
+
+    /// Returns the stats of the scope `label`.
+    pub fn get(&self, label: &str) -> Option<&ScopeStats> {
+        self.scopes.get(label)
+    }
+
+    /// Returns an iterator over the labels and stats of the scopes, ordered by label.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ScopeStats)> {
+        self.scopes.iter().map(|(label, stats)| (label.as_str(), stats))
+    }
+
+    /// Returns `true` if no scopes have been added.
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.scopes.keys().map(String::len).max().unwrap_or(0).max("label".len());
+
+        writeln!(
+            f,
+            "{:<width$} {:>8} {:>12} {:>10} {:>10} {:>10}",
+            "label", "count", "total", "mean", "min", "max"
+        )?;
+        for (label, stats) in &self.scopes {
+            writeln!(
+                f,
+                "{:<width$} {:>8} {:>12} {:>10} {:>10} {:>10}",
+                label,
+                stats.count,
+                stats.total,
+                stats.mean(),
+                stats.min,
+                stats.max
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_scope_log(log: &str) -> Option<(&str, u64)> {
+    let message = log.strip_prefix(PROGRAM_LOG)?.strip_prefix(SCOPE_LOG_PREFIX)?;
+    let (label, units) = message.rsplit_once(": ")?;
+
+    Some((label, units.parse().ok()?))
+}
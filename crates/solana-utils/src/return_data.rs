@@ -0,0 +1,246 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use borsh_size::BorshSize;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program::MAX_RETURN_DATA;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+use crate::{log, pubkeys_eq};
+
+/// Sets the return data of the current instruction to the borsh serialization of `value`.
+///
+/// The value is serialized into a buffer on the stack, so no heap allocation is made. The buffer
+/// is [`MAX_RETURN_DATA`] bytes, as the length of an array cannot depend on [`BorshSize::MAX_SIZE`]
+/// on stable Rust, but only the first `T::MAX_SIZE` bytes are written when it is bounded.
+/// Otherwise, the size of the value is computed with [`BorshSize::borsh_size`], which panics for
+/// types that do not implement it.
+///
+/// Returns [`ProgramError::InvalidArgument`] if the serialized value is larger than
+/// [`MAX_RETURN_DATA`].
+///
+/// # Example
+///
+/// ```
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::{get_return_data, set_return_data};
+///
+/// # let program_id = Pubkey::default();
+/// // In the callee.
+/// set_return_data(&42u64)?;
+///
+/// // In the caller, after the cross-program invocation.
+/// let value: Option<u64> = get_return_data(&program_id)?;
+/// # Ok::<(), solana_program::program_error::ProgramError>(())
+/// ```
+pub fn set_return_data<T>(value: &T) -> ProgramResult
+where
+    T: BorshSerialize + BorshSize,
+{
+    let size = match T::MAX_SIZE {
+        Some(max) if max <= MAX_RETURN_DATA => max,
+        _ => value.borsh_size(),
+    };
+    if size > MAX_RETURN_DATA {
+        log!("Error: Return data of {} bytes exceeds the maximum of {}", size, MAX_RETURN_DATA);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut buf = [0; MAX_RETURN_DATA];
+    let mut writer = &mut buf[..size];
+
+    if let Err(err) = value.serialize(&mut writer) {
+        log!("Error: Failed to serialize return data: {}", err);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let len = size - writer.len();
+
+    solana_program::program::set_return_data(&buf[..len]);
+
+    Ok(())
+}
+
+/// Gets the return data set by the last cross-program invocation, deserialized as `T`.
+///
+/// The return data is read into a buffer of [`MAX_RETURN_DATA`] bytes on the stack, of which only
+/// the first `T::MAX_SIZE` bytes are written when it is bounded.
+///
+/// Returns `None` if no return data was set.
+///
+/// Returns [`ProgramError::IncorrectProgramId`] if the return data was set by a program other than
+/// `expected_program_id`, or [`ProgramError::InvalidInstructionData`] if the return data is not a
+/// valid borsh serialization of `T`.
+pub fn get_return_data<T>(expected_program_id: &Pubkey) -> Result<Option<T>, ProgramError>
+where
+    T: BorshDeserialize + BorshSize,
+{
+    #[cfg(target_os = "solana")]
+    let (program_id, buf, len) = {
+        let mut buf = [0; MAX_RETURN_DATA];
+        let mut program_id = Pubkey::default();
+        let max = T::MAX_SIZE.map_or(MAX_RETURN_DATA, |max| max.min(MAX_RETURN_DATA));
+
+        // The full length of the return data is returned, even if only `max` bytes are copied.
+        let len = unsafe {
+            solana_program::syscalls::sol_get_return_data(
+                buf.as_mut_ptr(),
+                max as u64,
+                &mut program_id,
+            )
+        };
+
+        (program_id, buf, len as usize)
+    };
+
+    #[cfg(not(target_os = "solana"))]
+    let (program_id, buf, len) = match solana_program::program::get_return_data() {
+        Some((program_id, data)) => {
+            let len = data.len();
+            (program_id, data, len)
+        }
+        None => (Pubkey::default(), Vec::new(), 0),
+    };
+
+    if len == 0 {
+        return Ok(None);
+    }
+
+    if !pubkeys_eq(&program_id, expected_program_id) {
+        log!(
+            "Error: Expected return data from program {}, got return data from {}",
+            expected_program_id,
+            program_id,
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if len < T::MIN_SIZE || len > T::MAX_SIZE.unwrap_or(MAX_RETURN_DATA) {
+        log!("Error: Unexpected return data length {}", len);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    match borsh::from_slice(&buf[..len]) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) => {
+            log!("Error: Failed to deserialize return data: {}", err);
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::instruction::Instruction;
+
+    use super::*;
+    use crate::invoke::invoke;
+    use crate::test_runtime::{process_instruction, register_program};
+
+    /// Runs `test` in a program, with `callee` registered as a program that sets the return data
+    /// to `data`, if any.
+    fn run(callee: &Pubkey, data: Option<Vec<u8>>, test: impl FnOnce() -> ProgramResult) {
+        register_program(*callee, move |_, _, _| {
+            if let Some(data) = &data {
+                solana_program::program::set_return_data(data);
+            }
+            Ok(())
+        });
+
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        process_instruction(&instruction, &mut [], |_, _, _| test()).unwrap();
+    }
+
+    fn call(program_id: &Pubkey) -> ProgramResult {
+        invoke(&Instruction::new_with_bytes(*program_id, &[], vec![]), &[])
+    }
+
+    #[test]
+    fn round_trip() {
+        let callee = Pubkey::new_unique();
+
+        register_program(callee, |_, _, _| set_return_data(&(42u64, *b"abc")));
+
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        process_instruction(&instruction, &mut [], |_, _, _| {
+            assert_eq!(get_return_data::<(u64, [u8; 3])>(&callee), Ok(None));
+
+            call(&callee)?;
+            assert_eq!(get_return_data(&callee), Ok(Some((42u64, *b"abc"))));
+            assert_eq!(
+                get_return_data::<(u64, [u8; 3])>(&Pubkey::new_unique()),
+                Err(ProgramError::IncorrectProgramId),
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn unexpected_length() {
+        let callee = Pubkey::new_unique();
+
+        run(&callee, Some(vec![1, 2]), || {
+            call(&callee)?;
+            assert_eq!(get_return_data::<u64>(&callee), Err(ProgramError::InvalidInstructionData));
+            assert_eq!(get_return_data::<u8>(&callee), Err(ProgramError::InvalidInstructionData));
+            assert_eq!(get_return_data::<[u8; 2]>(&callee), Ok(Some([1, 2])));
+            Ok(())
+        });
+
+        // The data is long enough, but is not a valid serialization.
+        run(&callee, Some(vec![2]), || {
+            call(&callee)?;
+            assert_eq!(get_return_data::<bool>(&callee), Err(ProgramError::InvalidInstructionData));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn oversized() {
+        let callee = Pubkey::new_unique();
+
+        run(&callee, None, || {
+            assert_eq!(
+                set_return_data(&vec![0u8; MAX_RETURN_DATA]),
+                Err(ProgramError::InvalidArgument)
+            );
+            assert_eq!(
+                set_return_data(&[0u8; MAX_RETURN_DATA + 1]),
+                Err(ProgramError::InvalidArgument)
+            );
+
+            // The length prefix leaves room for `MAX_RETURN_DATA - 4` bytes.
+            set_return_data(&vec![0u8; MAX_RETURN_DATA - 4])?;
+            assert_eq!(
+                solana_program::program::get_return_data().unwrap().1.len(),
+                MAX_RETURN_DATA
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn cleared_by_invocations() {
+        let (callee, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        register_program(other, |_, _, _| Ok(()));
+
+        run(&callee, Some(vec![1]), || {
+            set_return_data(&1u8)?;
+            call(&callee)?;
+            assert_eq!(get_return_data(&callee), Ok(Some(1u8)));
+
+            // Invoking a program clears the return data, even if the program sets none.
+            call(&other)?;
+            assert_eq!(get_return_data::<u8>(&callee), Ok(None));
+
+            set_return_data(&2u8)?;
+            Ok(())
+        });
+
+        // Each instruction starts without return data.
+        run(&callee, None, || {
+            assert_eq!(get_return_data::<u8>(&Pubkey::default()), Ok(None));
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,523 @@
+//! Checks of the signatures verified by the ed25519 and secp256k1 precompiles.
+//!
+//! The precompiles verify the signatures of their instructions before the transaction is
+//! executed, so a program can rely on a signature by checking, through the instructions sysvar,
+//! that the transaction contains a precompile instruction for the expected signer and message.
+
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+use solana_program::{ed25519_program, secp256k1_program};
+
+use crate::introspection::{Instructions, IntrospectedInstruction};
+use crate::{bytes_eq, log, pubkeys_eq};
+
+/// The length of an Ethereum address, which identifies secp256k1 signers.
+pub const ETH_ADDRESS_LEN: usize = 20;
+
+/// The offsets of a signature in the data of an ed25519 precompile instruction.
+///
+/// An instruction index of `u16::MAX` refers to the precompile instruction itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ed25519SignatureOffsets {
+    pub signature_offset: u16,
+    pub signature_instruction_index: u16,
+    pub public_key_offset: u16,
+    pub public_key_instruction_index: u16,
+    pub message_data_offset: u16,
+    pub message_data_size: u16,
+    pub message_instruction_index: u16,
+}
+
+impl Ed25519SignatureOffsets {
+    /// The length of the serialized offsets.
+    pub const LEN: usize = 14;
+
+    /// The offset of the first offsets in the instruction data, after the number of signatures
+    /// and a padding byte.
+    pub const START: usize = 2;
+
+    /// Reads the offsets of the signature at `index` from the data of an ed25519 precompile
+    /// instruction.
+    pub fn read(data: &[u8], index: usize) -> Option<Self> {
+        let start = Self::START + index * Self::LEN;
+        let bytes = data.get(start..start + Self::LEN)?;
+        let read = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+
+        Some(Ed25519SignatureOffsets {
+            signature_offset: read(0),
+            signature_instruction_index: read(2),
+            public_key_offset: read(4),
+            public_key_instruction_index: read(6),
+            message_data_offset: read(8),
+            message_data_size: read(10),
+            message_instruction_index: read(12),
+        })
+    }
+}
+
+/// The offsets of a signature in the data of a secp256k1 precompile instruction.
+///
+/// The instruction indices refer to the instructions of the transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Secp256k1SignatureOffsets {
+    pub signature_offset: u16,
+    pub signature_instruction_index: u8,
+    pub eth_address_offset: u16,
+    pub eth_address_instruction_index: u8,
+    pub message_data_offset: u16,
+    pub message_data_size: u16,
+    pub message_instruction_index: u8,
+}
+
+impl Secp256k1SignatureOffsets {
+    /// The length of the serialized offsets.
+    pub const LEN: usize = 11;
+
+    /// The offset of the first offsets in the instruction data, after the number of signatures.
+    pub const START: usize = 1;
+
+    /// Reads the offsets of the signature at `index` from the data of a secp256k1 precompile
+    /// instruction.
+    pub fn read(data: &[u8], index: usize) -> Option<Self> {
+        let start = Self::START + index * Self::LEN;
+        let bytes = data.get(start..start + Self::LEN)?;
+        let read = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+
+        Some(Secp256k1SignatureOffsets {
+            signature_offset: read(0),
+            signature_instruction_index: bytes[2],
+            eth_address_offset: read(3),
+            eth_address_instruction_index: bytes[5],
+            message_data_offset: read(6),
+            message_data_size: read(8),
+            message_instruction_index: bytes[10],
+        })
+    }
+}
+
+/// Checks that the instruction at `ix_index` is an ed25519 precompile instruction which verifies
+/// a signature of `expected_message` by `expected_pubkey`.
+///
+/// Only signatures whose signature, public key and message are all read from the precompile
+/// instruction itself are considered. Fails with [`ProgramError::MissingRequiredSignature`] if
+/// none matches.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::introspection::Instructions;
+/// use solana_utils::sigverify::verify_ed25519_ix;
+/// # use solana_program::account_info::AccountInfo;
+/// # use solana_program::ed25519_program;
+/// # use solana_program::sysvar::instructions::{self, construct_instructions_data};
+/// # use solana_program::sysvar::instructions::BorrowedInstruction;
+///
+/// fn check_order(instructions: &Instructions, authority: &Pubkey, order: &[u8]) -> ProgramResult {
+///     // The client places the ed25519 instruction right before the current instruction.
+///     let ix_index = instructions.current_index().wrapping_sub(1);
+///     verify_ed25519_ix(instructions, authority, order, ix_index)
+/// }
+/// #
+/// # let (authority, order) = (Pubkey::new_unique(), b"order");
+/// # let mut ed25519_data = vec![1, 0];
+/// # for offset in [16u16, u16::MAX, 80, u16::MAX, 112, 5, u16::MAX] {
+/// #     ed25519_data.extend_from_slice(&offset.to_le_bytes());
+/// # }
+/// # ed25519_data.extend_from_slice(&[0; 64]);
+/// # ed25519_data.extend_from_slice(authority.as_ref());
+/// # ed25519_data.extend_from_slice(order);
+/// # let program_id = Pubkey::new_unique();
+/// # let mut data = construct_instructions_data(&[
+/// #     BorrowedInstruction {
+/// #         program_id: &ed25519_program::ID,
+/// #         accounts: vec![],
+/// #         data: &ed25519_data,
+/// #     },
+/// #     BorrowedInstruction { program_id: &program_id, accounts: vec![], data: &[] },
+/// # ]);
+/// # instructions::store_current_index(&mut data, 1);
+/// # let mut lamports = 0;
+/// # let sysvar = AccountInfo::new(
+/// #     &instructions::ID, false, false, &mut lamports, &mut data, &program_id, false, 0,
+/// # );
+/// # let instructions = Instructions::new(&sysvar).unwrap();
+/// # check_order(&instructions, &authority, order).unwrap();
+/// # assert!(check_order(&instructions, &program_id, order).is_err());
+/// ```
+pub fn verify_ed25519_ix(
+    instructions: &Instructions,
+    expected_pubkey: &Pubkey,
+    expected_message: &[u8],
+    ix_index: usize,
+) -> ProgramResult {
+    let instruction = precompile_instruction(instructions, ix_index, &ed25519_program::ID)?;
+    let data = instruction.data();
+
+    let num_signatures = data.first().copied().unwrap_or(0) as usize;
+    let same_instruction = |index: u16| index == u16::MAX || index as usize == ix_index;
+
+    for i in 0..num_signatures {
+        let offsets = match Ed25519SignatureOffsets::read(data, i) {
+            Some(offsets) => offsets,
+            None => return Err(malformed(ix_index)),
+        };
+
+        if !same_instruction(offsets.signature_instruction_index)
+            || !same_instruction(offsets.public_key_instruction_index)
+            || !same_instruction(offsets.message_instruction_index)
+        {
+            continue;
+        }
+
+        let pubkey = slice(data, offsets.public_key_offset, PUBKEY_BYTES as u16);
+        let message = slice(data, offsets.message_data_offset, offsets.message_data_size);
+
+        if let (Some(pubkey), Some(message)) = (pubkey, message) {
+            if bytes_eq(pubkey, expected_pubkey.as_ref()) && bytes_eq(message, expected_message) {
+                return Ok(());
+            }
+        }
+    }
+
+    log!(
+        "Error: Instruction {} does not verify an ed25519 signature by {} of the expected message",
+        ix_index,
+        expected_pubkey,
+    );
+
+    Err(ProgramError::MissingRequiredSignature)
+}
+
+/// Checks that the instruction at `ix_index` is a secp256k1 precompile instruction which verifies
+/// a signature of `expected_message` by the signer with the Ethereum address
+/// `expected_eth_address`.
+///
+/// Only signatures whose signature, Ethereum address and message are all read from the
+/// precompile instruction itself are considered. Fails with
+/// [`ProgramError::MissingRequiredSignature`] if none matches.
+pub fn verify_secp256k1_ix(
+    instructions: &Instructions,
+    expected_eth_address: &[u8; ETH_ADDRESS_LEN],
+    expected_message: &[u8],
+    ix_index: usize,
+) -> ProgramResult {
+    let instruction = precompile_instruction(instructions, ix_index, &secp256k1_program::ID)?;
+    let data = instruction.data();
+
+    let num_signatures = data.first().copied().unwrap_or(0) as usize;
+    let same_instruction = |index: u8| index as usize == ix_index;
+
+    for i in 0..num_signatures {
+        let offsets = match Secp256k1SignatureOffsets::read(data, i) {
+            Some(offsets) => offsets,
+            None => return Err(malformed(ix_index)),
+        };
+
+        if !same_instruction(offsets.signature_instruction_index)
+            || !same_instruction(offsets.eth_address_instruction_index)
+            || !same_instruction(offsets.message_instruction_index)
+        {
+            continue;
+        }
+
+        let eth_address = slice(data, offsets.eth_address_offset, ETH_ADDRESS_LEN as u16);
+        let message = slice(data, offsets.message_data_offset, offsets.message_data_size);
+
+        if let (Some(eth_address), Some(message)) = (eth_address, message) {
+            if bytes_eq(eth_address, expected_eth_address) && bytes_eq(message, expected_message) {
+                return Ok(());
+            }
+        }
+    }
+
+    log!(
+        "Error: Instruction {} does not verify a secp256k1 signature by {} of the expected message",
+        ix_index,
+        expected_eth_address,
+    );
+
+    Err(ProgramError::MissingRequiredSignature)
+}
+
+fn precompile_instruction<'a>(
+    instructions: &'a Instructions,
+    ix_index: usize,
+    program_id: &Pubkey,
+) -> Result<IntrospectedInstruction<'a>, ProgramError> {
+    let instruction = match instructions.get(ix_index) {
+        Some(instruction) => instruction,
+        None => {
+            log!("Error: Instruction {} does not exist", ix_index);
+
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+
+    if !pubkeys_eq(instruction.program_id(), program_id) {
+        log!("Error: Instruction {} does not invoke {}", ix_index, program_id);
+
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    Ok(instruction)
+}
+
+#[inline]
+fn slice(data: &[u8], offset: u16, len: u16) -> Option<&[u8]> {
+    let offset = offset as usize;
+    data.get(offset..offset + len as usize)
+}
+
+#[cold]
+fn malformed(ix_index: usize) -> ProgramError {
+    log!("Error: Instruction {} has malformed signature offsets", ix_index);
+    ProgramError::InvalidInstructionData
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::account_info::AccountInfo;
+    use solana_program::sysvar::instructions::{
+        self, construct_instructions_data, store_current_index, BorrowedInstruction,
+    };
+
+    use super::*;
+
+    const MESSAGE: &[u8] = b"message";
+
+    /// Serializes an ed25519 instruction verifying a signature by `pubkey` of `message`, with
+    /// `index` as the instruction index of each offset.
+    fn ed25519_data(pubkey: &Pubkey, message: &[u8], index: u16) -> Vec<u8> {
+        let public_key_offset =
+            (Ed25519SignatureOffsets::START + Ed25519SignatureOffsets::LEN) as u16;
+        let signature_offset = public_key_offset + PUBKEY_BYTES as u16;
+        let message_data_offset = signature_offset + 64;
+
+        let mut data = vec![1, 0];
+        for offset in [
+            signature_offset,
+            index,
+            public_key_offset,
+            index,
+            message_data_offset,
+            message.len() as u16,
+            index,
+        ] {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(pubkey.as_ref());
+        data.extend_from_slice(&[0; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    /// Serializes a secp256k1 instruction verifying a signature by `eth_address` of `message`,
+    /// with `index` as the instruction index of each offset.
+    fn secp256k1_data(eth_address: &[u8; ETH_ADDRESS_LEN], message: &[u8], index: u8) -> Vec<u8> {
+        let eth_address_offset =
+            (Secp256k1SignatureOffsets::START + Secp256k1SignatureOffsets::LEN) as u16;
+        let signature_offset = eth_address_offset + ETH_ADDRESS_LEN as u16;
+        let message_data_offset = signature_offset + 65;
+
+        let mut data = vec![1];
+        data.extend_from_slice(&signature_offset.to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(&eth_address_offset.to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(&message_data_offset.to_le_bytes());
+        data.extend_from_slice(&(message.len() as u16).to_le_bytes());
+        data.push(index);
+        data.extend_from_slice(eth_address);
+        data.extend_from_slice(&[0; 65]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    /// Runs `f` with the instructions sysvar of a transaction made of `instructions`, followed by
+    /// the current instruction.
+    fn with_instructions<R>(
+        instructions: &[(Pubkey, Vec<u8>)],
+        f: impl FnOnce(&Instructions) -> R,
+    ) -> R {
+        let program_id = Pubkey::new_unique();
+        let mut borrowed: Vec<_> = instructions
+            .iter()
+            .map(|(program_id, data)| BorrowedInstruction { program_id, accounts: vec![], data })
+            .collect();
+        borrowed.push(BorrowedInstruction { program_id: &program_id, accounts: vec![], data: &[] });
+
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, instructions.len() as u16);
+
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &instructions::ID,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &solana_program::sysvar::ID,
+            false,
+            0,
+        );
+        let instructions = Instructions::new(&account).unwrap();
+        f(&instructions)
+    }
+
+    #[test]
+    fn ed25519() {
+        let pubkey = Pubkey::new_unique();
+        let instructions = [
+            (ed25519_program::ID, ed25519_data(&pubkey, MESSAGE, u16::MAX)),
+            (ed25519_program::ID, ed25519_data(&pubkey, MESSAGE, 1)),
+        ];
+
+        with_instructions(&instructions, |instructions| {
+            assert_eq!(verify_ed25519_ix(instructions, &pubkey, MESSAGE, 0), Ok(()));
+            assert_eq!(verify_ed25519_ix(instructions, &pubkey, MESSAGE, 1), Ok(()));
+        });
+    }
+
+    #[test]
+    fn ed25519_mismatch() {
+        let pubkey = Pubkey::new_unique();
+        let instructions = [(ed25519_program::ID, ed25519_data(&pubkey, MESSAGE, u16::MAX))];
+
+        with_instructions(&instructions, |instructions| {
+            assert_eq!(
+                verify_ed25519_ix(instructions, &Pubkey::new_unique(), MESSAGE, 0),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, b"other", 0),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, &MESSAGE[..4], 0),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+        });
+    }
+
+    #[test]
+    fn ed25519_other_instruction() {
+        // The signature is read from the current instruction, which the program controls.
+        let pubkey = Pubkey::new_unique();
+        let instructions = [(ed25519_program::ID, ed25519_data(&pubkey, MESSAGE, 1))];
+
+        with_instructions(&instructions, |instructions| {
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 0),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+        });
+    }
+
+    #[test]
+    fn ed25519_malformed() {
+        let pubkey = Pubkey::new_unique();
+
+        // The instruction declares a signature but its offsets are cut short.
+        let mut truncated = ed25519_data(&pubkey, MESSAGE, u16::MAX);
+        truncated.truncate(Ed25519SignatureOffsets::START + Ed25519SignatureOffsets::LEN - 1);
+
+        // The offsets point past the end of the instruction data.
+        let mut out_of_bounds = ed25519_data(&pubkey, MESSAGE, u16::MAX);
+        out_of_bounds[6..8].copy_from_slice(&u16::MAX.to_le_bytes());
+
+        let instructions = [
+            (ed25519_program::ID, truncated),
+            (ed25519_program::ID, out_of_bounds),
+            (ed25519_program::ID, vec![]),
+        ];
+
+        with_instructions(&instructions, |instructions| {
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 0),
+                Err(ProgramError::InvalidInstructionData),
+            );
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 1),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 2),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+        });
+    }
+
+    #[test]
+    fn wrong_instruction() {
+        let pubkey = Pubkey::new_unique();
+        let instructions = [(secp256k1_program::ID, ed25519_data(&pubkey, MESSAGE, u16::MAX))];
+
+        with_instructions(&instructions, |instructions| {
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 0),
+                Err(ProgramError::IncorrectProgramId),
+            );
+            // The current instruction is not a precompile instruction either.
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 1),
+                Err(ProgramError::IncorrectProgramId),
+            );
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, 2),
+                Err(ProgramError::InvalidArgument),
+            );
+            assert_eq!(
+                verify_ed25519_ix(instructions, &pubkey, MESSAGE, usize::MAX),
+                Err(ProgramError::InvalidArgument),
+            );
+        });
+    }
+
+    #[test]
+    fn secp256k1() {
+        let eth_address = [7; ETH_ADDRESS_LEN];
+        let instructions = [
+            (ed25519_program::ID, ed25519_data(&Pubkey::new_unique(), MESSAGE, u16::MAX)),
+            (secp256k1_program::ID, secp256k1_data(&eth_address, MESSAGE, 1)),
+            (secp256k1_program::ID, secp256k1_data(&eth_address, MESSAGE, 0)),
+        ];
+
+        with_instructions(&instructions, |instructions| {
+            assert_eq!(verify_secp256k1_ix(instructions, &eth_address, MESSAGE, 1), Ok(()));
+            assert_eq!(
+                verify_secp256k1_ix(instructions, &[8; ETH_ADDRESS_LEN], MESSAGE, 1),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+            assert_eq!(
+                verify_secp256k1_ix(instructions, &eth_address, b"other", 1),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+            // The offsets refer to another instruction.
+            assert_eq!(
+                verify_secp256k1_ix(instructions, &eth_address, MESSAGE, 2),
+                Err(ProgramError::MissingRequiredSignature),
+            );
+            assert_eq!(
+                verify_secp256k1_ix(instructions, &eth_address, MESSAGE, 0),
+                Err(ProgramError::IncorrectProgramId),
+            );
+        });
+    }
+
+    #[test]
+    fn secp256k1_malformed() {
+        let eth_address = [7; ETH_ADDRESS_LEN];
+        let mut data = secp256k1_data(&eth_address, MESSAGE, 0);
+        data.truncate(Secp256k1SignatureOffsets::START + Secp256k1SignatureOffsets::LEN - 1);
+
+        with_instructions(&[(secp256k1_program::ID, data)], |instructions| {
+            assert_eq!(
+                verify_secp256k1_ix(instructions, &eth_address, MESSAGE, 0),
+                Err(ProgramError::InvalidInstructionData),
+            );
+        });
+    }
+}
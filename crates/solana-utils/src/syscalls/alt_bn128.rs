@@ -0,0 +1,166 @@
+use solana_program::alt_bn128::prelude::{
+    ALT_BN128_ADDITION_INPUT_LEN, ALT_BN128_ADDITION_OUTPUT_LEN,
+    ALT_BN128_MULTIPLICATION_INPUT_LEN, ALT_BN128_MULTIPLICATION_OUTPUT_LEN,
+    ALT_BN128_PAIRING_ELEMENT_LEN, ALT_BN128_PAIRING_OUTPUT_LEN,
+};
+pub use solana_program::alt_bn128::AltBn128Error;
+
+/// Adds two alt_bn128 G1 points, each encoded as big-endian `x` and `y` coordinates.
+///
+/// Inputs shorter than 128 bytes are zero-padded.
+#[inline]
+pub fn alt_bn128_addition(
+    input: &[u8],
+) -> Result<[u8; ALT_BN128_ADDITION_OUTPUT_LEN], AltBn128Error> {
+    if input.len() > ALT_BN128_ADDITION_INPUT_LEN {
+        return Err(AltBn128Error::InvalidInputData);
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        group_op(solana_program::alt_bn128::prelude::ALT_BN128_ADD, input)
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        to_array(solana_program::alt_bn128::prelude::alt_bn128_addition(input))
+    }
+}
+
+/// Multiplies an alt_bn128 G1 point by a big-endian 32-byte scalar.
+///
+/// Inputs shorter than 128 bytes are zero-padded.
+#[inline]
+pub fn alt_bn128_multiplication(
+    input: &[u8],
+) -> Result<[u8; ALT_BN128_MULTIPLICATION_OUTPUT_LEN], AltBn128Error> {
+    if input.len() > ALT_BN128_MULTIPLICATION_INPUT_LEN {
+        return Err(AltBn128Error::InvalidInputData);
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        group_op(solana_program::alt_bn128::prelude::ALT_BN128_MUL, input)
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        to_array(solana_program::alt_bn128::prelude::alt_bn128_multiplication(input))
+    }
+}
+
+/// Checks the pairing of a sequence of alt_bn128 (G1, G2) point pairs.
+///
+/// Returns a big-endian 32-byte integer, which is 1 if the pairing check passes and 0 otherwise.
+#[inline]
+pub fn alt_bn128_pairing(
+    input: &[u8],
+) -> Result<[u8; ALT_BN128_PAIRING_OUTPUT_LEN], AltBn128Error> {
+    if input.len() % ALT_BN128_PAIRING_ELEMENT_LEN != 0 {
+        return Err(AltBn128Error::InvalidInputData);
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        group_op(solana_program::alt_bn128::prelude::ALT_BN128_PAIRING, input)
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        to_array(solana_program::alt_bn128::prelude::alt_bn128_pairing(input))
+    }
+}
+
+#[cfg(target_os = "solana")]
+#[inline]
+fn group_op<const N: usize>(op: u64, input: &[u8]) -> Result<[u8; N], AltBn128Error> {
+    let mut result = [0; N];
+    let status = unsafe {
+        solana_program::syscalls::sol_alt_bn128_group_op(
+            op,
+            input.as_ptr(),
+            input.len() as u64,
+            result.as_mut_ptr(),
+        )
+    };
+
+    match status {
+        0 => Ok(result),
+        error => Err(AltBn128Error::from(error)),
+    }
+}
+
+/// Converts the result of a host implementation, reporting every error as `InvalidInputData` like
+/// the syscall.
+#[cfg(not(target_os = "solana"))]
+fn to_array<const N: usize>(
+    result: Result<Vec<u8>, AltBn128Error>,
+) -> Result<[u8; N], AltBn128Error> {
+    result.ok().and_then(|result| result.try_into().ok()).ok_or(AltBn128Error::InvalidInputData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscalls::hex;
+
+    const GENERATOR: &str = "0000000000000000000000000000000000000000000000000000000000000001\
+                             0000000000000000000000000000000000000000000000000000000000000002";
+    const DOUBLE: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+                          15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+    /// The point (1, 3), which is not on the curve `y^2 = x^3 + 3`.
+    const INVALID: &str = "0000000000000000000000000000000000000000000000000000000000000001\
+                           0000000000000000000000000000000000000000000000000000000000000003";
+
+    #[test]
+    fn addition() {
+        let [generator, double, invalid]: [[u8; 64]; 3] =
+            [hex(GENERATOR), hex(DOUBLE), hex(INVALID)];
+
+        let result = alt_bn128_addition(&[generator, generator].concat());
+        assert_eq!(result, Ok(double));
+        assert_eq!(alt_bn128_addition(&generator), Ok(generator));
+
+        let result = alt_bn128_addition(&[generator, invalid].concat());
+        assert_eq!(result, Err(AltBn128Error::InvalidInputData));
+
+        let result = alt_bn128_addition(&[0; ALT_BN128_ADDITION_INPUT_LEN + 1]);
+        assert_eq!(result, Err(AltBn128Error::InvalidInputData));
+
+        // Coordinates larger than the field modulus.
+        let result = alt_bn128_addition(&[0xff; ALT_BN128_ADDITION_INPUT_LEN]);
+        assert_eq!(result, Err(AltBn128Error::InvalidInputData));
+    }
+
+    #[test]
+    fn multiplication() {
+        let [generator, double, invalid]: [[u8; 64]; 3] =
+            [hex(GENERATOR), hex(DOUBLE), hex(INVALID)];
+        let mut two = [0; 32];
+        two[31] = 2;
+
+        let result = alt_bn128_multiplication(&[&generator[..], &two].concat());
+        assert_eq!(result, Ok(double));
+
+        let result = alt_bn128_multiplication(&[&invalid[..], &two].concat());
+        assert_eq!(result, Err(AltBn128Error::InvalidInputData));
+
+        let result = alt_bn128_multiplication(&[0; ALT_BN128_MULTIPLICATION_INPUT_LEN + 1]);
+        assert_eq!(result, Err(AltBn128Error::InvalidInputData));
+    }
+
+    #[test]
+    fn pairing() {
+        let mut one = [0; ALT_BN128_PAIRING_OUTPUT_LEN];
+        one[ALT_BN128_PAIRING_OUTPUT_LEN - 1] = 1;
+        assert_eq!(alt_bn128_pairing(&[]), Ok(one));
+
+        // A pair of identity points.
+        let result = alt_bn128_pairing(&[0; ALT_BN128_PAIRING_ELEMENT_LEN]);
+        assert_eq!(result, Ok(one));
+
+        let mut input = [0; ALT_BN128_PAIRING_ELEMENT_LEN];
+        input[..64].copy_from_slice(&hex::<64>(INVALID));
+        assert_eq!(alt_bn128_pairing(&input), Err(AltBn128Error::InvalidInputData));
+
+        let result = alt_bn128_pairing(&[0; ALT_BN128_PAIRING_ELEMENT_LEN - 1]);
+        assert_eq!(result, Err(AltBn128Error::InvalidInputData));
+    }
+}
@@ -0,0 +1,177 @@
+/// A curve25519 representation supported by the curve syscalls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Curve25519 {
+    /// Compressed Edwards points, as used by ed25519.
+    Edwards = 0,
+    /// Compressed Ristretto points.
+    Ristretto = 1,
+}
+
+/// A group operation supported by [`curve25519_group_op`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum Curve25519GroupOp {
+    /// Adds two points.
+    Add = 0,
+    /// Subtracts the right point from the left point.
+    Subtract = 1,
+    /// Multiplies the right point by the left scalar.
+    Multiply = 2,
+}
+
+/// Checks whether `point` is a valid compressed point of `curve`.
+#[inline]
+pub fn curve25519_validate_point(curve: Curve25519, point: &[u8; 32]) -> bool {
+    #[cfg(target_os = "solana")]
+    {
+        let mut result = 0u8;
+        let status = unsafe {
+            solana_program::syscalls::sol_curve_validate_point(
+                curve as u64,
+                point.as_ptr(),
+                &mut result,
+            )
+        };
+        status == 0
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        use curve25519_dalek::edwards::CompressedEdwardsY;
+        use curve25519_dalek::ristretto::CompressedRistretto;
+
+        match curve {
+            Curve25519::Edwards => CompressedEdwardsY(*point).decompress().is_some(),
+            Curve25519::Ristretto => CompressedRistretto(*point).decompress().is_some(),
+        }
+    }
+}
+
+/// Applies the group operation `op` to `left` and `right`, returning the compressed result.
+///
+/// For [`Curve25519GroupOp::Multiply`], `left` is a canonical little-endian scalar and `right` is
+/// the point. Returns `None` if an input point is invalid or the scalar is not canonical.
+#[inline]
+pub fn curve25519_group_op(
+    curve: Curve25519,
+    op: Curve25519GroupOp,
+    left: &[u8; 32],
+    right: &[u8; 32],
+) -> Option<[u8; 32]> {
+    #[cfg(target_os = "solana")]
+    {
+        let mut result = [0; 32];
+        let status = unsafe {
+            solana_program::syscalls::sol_curve_group_op(
+                curve as u64,
+                op as u64,
+                left.as_ptr(),
+                right.as_ptr(),
+                result.as_mut_ptr(),
+            )
+        };
+
+        match status {
+            0 => Some(result),
+            _ => None,
+        }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        use curve25519_dalek::edwards::CompressedEdwardsY;
+        use curve25519_dalek::ristretto::CompressedRistretto;
+        use curve25519_dalek::scalar::Scalar;
+
+        match (curve, op) {
+            (Curve25519::Edwards, Curve25519GroupOp::Multiply) => {
+                let scalar = Scalar::from_canonical_bytes(*left)?;
+                let point = CompressedEdwardsY(*right).decompress()?;
+                Some((scalar * point).compress().to_bytes())
+            }
+            (Curve25519::Edwards, op) => {
+                let left = CompressedEdwardsY(*left).decompress()?;
+                let right = CompressedEdwardsY(*right).decompress()?;
+                let result = if op == Curve25519GroupOp::Add { left + right } else { left - right };
+                Some(result.compress().to_bytes())
+            }
+            (Curve25519::Ristretto, Curve25519GroupOp::Multiply) => {
+                let scalar = Scalar::from_canonical_bytes(*left)?;
+                let point = CompressedRistretto(*right).decompress()?;
+                Some((scalar * point).compress().to_bytes())
+            }
+            (Curve25519::Ristretto, op) => {
+                let left = CompressedRistretto(*left).decompress()?;
+                let right = CompressedRistretto(*right).decompress()?;
+                let result = if op == Curve25519GroupOp::Add { left + right } else { left - right };
+                Some(result.compress().to_bytes())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscalls::hex;
+
+    const EDWARDS_BASEPOINT: &str =
+        "5866666666666666666666666666666666666666666666666666666666666666";
+    const EDWARDS_DOUBLE: &str = "c9a3f86aae465f0e56513864510f3997561fa2c9e85ea21dc2292309f3cd6022";
+    const EDWARDS_IDENTITY: &str =
+        "0100000000000000000000000000000000000000000000000000000000000000";
+    /// A `y` coordinate with no corresponding `x` coordinate.
+    const EDWARDS_INVALID: &str =
+        "0200000000000000000000000000000000000000000000000000000000000000";
+
+    const RISTRETTO_BASEPOINT: &str =
+        "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76";
+    const RISTRETTO_DOUBLE: &str =
+        "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919";
+    const RISTRETTO_IDENTITY: &str =
+        "0000000000000000000000000000000000000000000000000000000000000000";
+    /// A negative field element, which is not a canonical encoding.
+    const RISTRETTO_INVALID: &str =
+        "0100000000000000000000000000000000000000000000000000000000000000";
+
+    const TWO: &str = "0200000000000000000000000000000000000000000000000000000000000000";
+    /// The curve order, which is not a canonical scalar.
+    const ORDER: &str = "edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010";
+
+    fn check(curve: Curve25519, [basepoint, double, identity, invalid]: [&str; 4]) {
+        let (basepoint, double, identity, invalid) =
+            (hex(basepoint), hex(double), hex(identity), hex(invalid));
+
+        assert!(curve25519_validate_point(curve, &basepoint));
+        assert!(curve25519_validate_point(curve, &identity));
+        assert!(!curve25519_validate_point(curve, &invalid));
+
+        let group_op =
+            |op, left: [u8; 32], right: [u8; 32]| curve25519_group_op(curve, op, &left, &right);
+
+        assert_eq!(group_op(Curve25519GroupOp::Add, basepoint, basepoint), Some(double));
+        assert_eq!(group_op(Curve25519GroupOp::Subtract, double, basepoint), Some(basepoint));
+        assert_eq!(group_op(Curve25519GroupOp::Subtract, basepoint, basepoint), Some(identity));
+        assert_eq!(group_op(Curve25519GroupOp::Multiply, hex(TWO), basepoint), Some(double));
+
+        assert_eq!(group_op(Curve25519GroupOp::Add, basepoint, invalid), None);
+        assert_eq!(group_op(Curve25519GroupOp::Subtract, invalid, basepoint), None);
+        assert_eq!(group_op(Curve25519GroupOp::Multiply, hex(TWO), invalid), None);
+        assert_eq!(group_op(Curve25519GroupOp::Multiply, hex(ORDER), basepoint), None);
+    }
+
+    #[test]
+    fn edwards() {
+        check(
+            Curve25519::Edwards,
+            [EDWARDS_BASEPOINT, EDWARDS_DOUBLE, EDWARDS_IDENTITY, EDWARDS_INVALID],
+        );
+    }
+
+    #[test]
+    fn ristretto() {
+        check(
+            Curve25519::Ristretto,
+            [RISTRETTO_BASEPOINT, RISTRETTO_DOUBLE, RISTRETTO_IDENTITY, RISTRETTO_INVALID],
+        );
+    }
+}
@@ -0,0 +1,166 @@
+pub use solana_program::poseidon::{Endianness, Parameters, PoseidonSyscallError};
+
+/// The length of a hash in bytes.
+pub const HASH_BYTES: usize = 32;
+
+/// The maximum number of inputs to [`poseidon`].
+pub const MAX_POSEIDON_INPUTS: usize = 12;
+
+/// Computes the SHA-256 hash of the concatenation of `vals`.
+#[inline]
+pub fn sha256(vals: &[&[u8]]) -> [u8; HASH_BYTES] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = [0; HASH_BYTES];
+        unsafe {
+            solana_program::syscalls::sol_sha256(
+                vals as *const _ as *const u8,
+                vals.len() as u64,
+                hash.as_mut_ptr(),
+            );
+        }
+        hash
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        solana_program::hash::hashv(vals).to_bytes()
+    }
+}
+
+/// Computes the Keccak-256 hash of the concatenation of `vals`.
+#[inline]
+pub fn keccak256(vals: &[&[u8]]) -> [u8; HASH_BYTES] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = [0; HASH_BYTES];
+        unsafe {
+            solana_program::syscalls::sol_keccak256(
+                vals as *const _ as *const u8,
+                vals.len() as u64,
+                hash.as_mut_ptr(),
+            );
+        }
+        hash
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        solana_program::keccak::hashv(vals).to_bytes()
+    }
+}
+
+/// Computes the BLAKE3 hash of the concatenation of `vals`.
+#[inline]
+pub fn blake3(vals: &[&[u8]]) -> [u8; HASH_BYTES] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = [0; HASH_BYTES];
+        unsafe {
+            solana_program::syscalls::sol_blake3(
+                vals as *const _ as *const u8,
+                vals.len() as u64,
+                hash.as_mut_ptr(),
+            );
+        }
+        hash
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        solana_program::blake3::hashv(vals).to_bytes()
+    }
+}
+
+/// Computes the Poseidon hash of `vals`, each of which is a field element of at most 32 bytes.
+///
+/// Unlike the other hash functions, `vals` are hashed as separate inputs rather than being
+/// concatenated.
+///
+/// Returns `InvalidNumberOfInputs` for more than [`MAX_POSEIDON_INPUTS`] inputs, and
+/// `InvalidParameters` for any other invalid input, which is the error reported by the syscall.
+#[inline]
+pub fn poseidon(
+    parameters: Parameters,
+    endianness: Endianness,
+    vals: &[&[u8]],
+) -> Result<[u8; HASH_BYTES], PoseidonSyscallError> {
+    // The syscall aborts the program instead of returning an error.
+    if vals.len() > MAX_POSEIDON_INPUTS {
+        return Err(PoseidonSyscallError::InvalidNumberOfInputs);
+    }
+
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = [0; HASH_BYTES];
+        let result = unsafe {
+            solana_program::syscalls::sol_poseidon(
+                parameters.into(),
+                endianness.into(),
+                vals as *const _ as *const u8,
+                vals.len() as u64,
+                hash.as_mut_ptr(),
+            )
+        };
+
+        match result {
+            0 => Ok(hash),
+            error => Err(PoseidonSyscallError::from(error)),
+        }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        solana_program::poseidon::hashv(parameters, endianness, vals)
+            .map(|hash| hash.to_bytes())
+            .map_err(|_| PoseidonSyscallError::InvalidParameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscalls::hex;
+
+    #[test]
+    fn known_vectors() {
+        assert_eq!(
+            sha256(&[b"a", b"bc"]),
+            hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            keccak256(&[]),
+            hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            blake3(&[b""]),
+            hex("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262")
+        );
+    }
+
+    #[test]
+    fn poseidon_vectors() {
+        let hash = hex("0d54e1938f8a8c1c7deb5e0355f26319207b84fe9ca2ce1b26e735c829821990");
+        let vals: &[&[u8]] = &[&[1; 32], &[2; 32]];
+
+        let result = poseidon(Parameters::Bn254X5, Endianness::BigEndian, vals);
+        assert_eq!(result.unwrap(), hash);
+
+        let mut reversed = hash;
+        reversed.reverse();
+        let result = poseidon(Parameters::Bn254X5, Endianness::LittleEndian, vals);
+        assert_eq!(result.unwrap(), reversed);
+    }
+
+    #[test]
+    fn poseidon_errors() {
+        let invalid: [&[&[u8]]; 3] = [&[], &[&[1; 33]], &[&[0xff; 32]]];
+        for vals in invalid {
+            let result = poseidon(Parameters::Bn254X5, Endianness::BigEndian, vals);
+            assert!(matches!(result, Err(PoseidonSyscallError::InvalidParameters)));
+        }
+
+        let vals = [&[1u8][..]; MAX_POSEIDON_INPUTS + 1];
+        let result = poseidon(Parameters::Bn254X5, Endianness::BigEndian, &vals);
+        assert!(matches!(result, Err(PoseidonSyscallError::InvalidNumberOfInputs)));
+
+        let result = poseidon(Parameters::Bn254X5, Endianness::BigEndian, &vals[1..]);
+        assert!(result.is_ok());
+    }
+}
@@ -1,3 +1,18 @@
+//! Syscall wrappers which fall back to equivalent pure-Rust implementations off-chain.
+//!
+//! The fallbacks return the same errors as the syscalls, which report every alt_bn128 and Poseidon
+//! failure with error code 1.
+
+mod alt_bn128;
+mod curve25519;
+mod hash;
+mod secp256k1;
+
+pub use self::alt_bn128::*;
+pub use self::curve25519::*;
+pub use self::hash::*;
+pub use self::secp256k1::*;
+
 /// Copies `count` bytes from `src` to `dst`. The source and destination must
 /// *not* overlap.
 ///
@@ -98,3 +113,14 @@ pub unsafe fn memset(dst: *mut u8, val: u8, count: usize) {
     #[cfg(not(target_os = "solana"))]
     std::ptr::write_bytes(dst, val, count);
 }
+
+/// Decodes a hex string into an array.
+#[cfg(test)]
+fn hex<const N: usize>(hex: &str) -> [u8; N] {
+    assert_eq!(hex.len(), 2 * N);
+    let mut bytes = [0; N];
+    for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).unwrap(), 16).unwrap();
+    }
+    bytes
+}
@@ -0,0 +1,76 @@
+pub use solana_program::secp256k1_recover::Secp256k1RecoverError;
+use solana_program::secp256k1_recover::{SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH};
+
+/// Recovers the uncompressed secp256k1 public key, without its `0x04` prefix, that signed the
+/// 32-byte message `hash` with `signature`.
+///
+/// `signature` is the 64-byte compact signature, and `recovery_id` must be in the range `0..4`.
+#[inline]
+pub fn secp256k1_recover(
+    hash: &[u8; 32],
+    recovery_id: u8,
+    signature: &[u8; SECP256K1_SIGNATURE_LENGTH],
+) -> Result<[u8; SECP256K1_PUBLIC_KEY_LENGTH], Secp256k1RecoverError> {
+    #[cfg(target_os = "solana")]
+    {
+        let mut pubkey = [0; SECP256K1_PUBLIC_KEY_LENGTH];
+        let result = unsafe {
+            solana_program::syscalls::sol_secp256k1_recover(
+                hash.as_ptr(),
+                recovery_id as u64,
+                signature.as_ptr(),
+                pubkey.as_mut_ptr(),
+            )
+        };
+
+        match result {
+            0 => Ok(pubkey),
+            error => Err(Secp256k1RecoverError::from(error)),
+        }
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        solana_program::secp256k1_recover::secp256k1_recover(hash, recovery_id, signature)
+            .map(|pubkey| pubkey.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscalls::{hex, keccak256};
+
+    const SIGNATURE: &str = "2a99880c06b5d600a532a98c2b66384c1c76ba0c165b7f233e9541ad33b6007d\
+                             3c64279197a569d307dbed301a56ee695c0d82bcb92d67b14eb7617977639d07";
+
+    /// The public key for the secret key `[1; 32]`.
+    const PUBKEY: &str = "1b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f\
+                          70beaf8f588b541507fed6a642c5ab42dfdf8120a7f639de5122d47a69a8e8d1";
+
+    #[test]
+    fn recover() {
+        let hash = keccak256(&[b"hello"]);
+        let signature = hex(SIGNATURE);
+
+        assert_eq!(secp256k1_recover(&hash, 1, &signature), Ok(hex(PUBKEY)));
+        assert_ne!(secp256k1_recover(&hash, 0, &signature), Ok(hex(PUBKEY)));
+
+        let other = keccak256(&[b"world"]);
+        assert_ne!(secp256k1_recover(&other, 1, &signature), Ok(hex(PUBKEY)));
+    }
+
+    #[test]
+    fn errors() {
+        let hash = keccak256(&[b"hello"]);
+        let signature = hex(SIGNATURE);
+
+        let result = secp256k1_recover(&hash, 4, &signature);
+        assert_eq!(result, Err(Secp256k1RecoverError::InvalidRecoveryId));
+
+        // `r` and `s` must be non-zero and less than the curve order.
+        for invalid in [[0; 64], [0xff; 64]] {
+            let result = secp256k1_recover(&hash, 1, &invalid);
+            assert_eq!(result, Err(Secp256k1RecoverError::InvalidSignature));
+        }
+    }
+}
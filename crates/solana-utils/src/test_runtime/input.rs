@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::mem::size_of;
+use std::ops::Range;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::{
+    deserialize, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER,
+};
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+
+use super::Account;
+
+thread_local! {
+    /// The address ranges of the buffers of the live inputs on the current thread.
+    static BUFFERS: RefCell<Vec<Range<usize>>> = const { RefCell::new(Vec::new()) };
+}
+
+// Offsets of the fields of a non-duplicate account, relative to its `dup_info` byte.
+const EXECUTABLE: usize = 3;
+const KEY: usize = 8;
+const OWNER: usize = KEY + size_of::<Pubkey>();
+const LAMPORTS: usize = OWNER + size_of::<Pubkey>();
+const DATA_LEN: usize = LAMPORTS + size_of::<u64>();
+const DATA: usize = DATA_LEN + size_of::<u64>();
+
+/// Program input serialized in the same layout the runtime uses for the `process_instruction`
+/// entrypoint of a program.
+///
+/// Account data is followed by [`MAX_PERMITTED_DATA_INCREASE`] bytes of spare capacity and the
+/// original data length is recorded in the account header, so the [`AccountInfo`]s returned by
+/// [`Input::deserialize`] can be reallocated exactly as they would be on-chain.
+pub struct Input {
+    // Backed by `u64`s to guarantee the alignment the runtime provides.
+    buffer: Vec<u64>,
+    accounts: Vec<SerializedAccount>,
+}
+
+#[derive(Clone, Copy)]
+enum Position {
+    Unique(usize),
+    Duplicate(u8),
+}
+
+struct SerializedAccount {
+    key: Pubkey,
+    offset: usize,
+    rent_epoch: u64,
+}
+
+impl Input {
+    /// Serializes the input for `instruction`.
+    ///
+    /// Every account referenced by the instruction must have its state present in `accounts`.
+    /// Accounts referenced more than once are serialized as duplicates of the first occurrence,
+    /// with signer and writable privileges merged across all occurrences.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state of an account referenced by the instruction is missing.
+    pub fn new(instruction: &Instruction, accounts: &[(Pubkey, Account)]) -> Input {
+        let metas = &instruction.accounts;
+
+        // Resolve duplicates and the merged privileges of each unique account.
+        let mut unique: Vec<(&Pubkey, bool, bool, &Account)> = Vec::with_capacity(metas.len());
+        let mut positions = Vec::with_capacity(metas.len());
+
+        for (index, meta) in metas.iter().enumerate() {
+            let first = metas[..index].iter().position(|other| other.pubkey == meta.pubkey);
+
+            match first {
+                Some(first) => {
+                    if let Position::Unique(index) = positions[first] {
+                        let (_, is_signer, is_writable, _) = &mut unique[index];
+
+                        *is_signer |= meta.is_signer;
+                        *is_writable |= meta.is_writable;
+                    }
+
+                    positions.push(Position::Duplicate(first as u8));
+                }
+                None => {
+                    let account = match accounts.iter().find(|(key, _)| *key == meta.pubkey) {
+                        Some((_, account)) => account,
+                        None => panic!("missing account state for {}", meta.pubkey),
+                    };
+
+                    positions.push(Position::Unique(unique.len()));
+                    unique.push((&meta.pubkey, meta.is_signer, meta.is_writable, account));
+                }
+            }
+        }
+
+        let mut len = size_of::<u64>();
+        for position in &positions {
+            len += match *position {
+                Position::Unique(index) => account_len(unique[index].3.data.len()),
+                Position::Duplicate(_) => size_of::<u64>(),
+            };
+        }
+        len += size_of::<u64>() + instruction.data.len() + size_of::<Pubkey>();
+
+        let mut input =
+            Input { buffer: vec![0; len.div_ceil(size_of::<u64>())], accounts: Vec::new() };
+        let mut serialized = Vec::with_capacity(unique.len());
+
+        let mut offset = 0;
+        {
+            let bytes = input.bytes_mut();
+
+            write(bytes, &mut offset, &(metas.len() as u64).to_le_bytes());
+
+            for position in positions {
+                match position {
+                    Position::Unique(index) => {
+                        let (key, is_signer, is_writable, account) = unique[index];
+
+                        serialized.push(SerializedAccount {
+                            key: *key,
+                            offset,
+                            rent_epoch: account.rent_epoch,
+                        });
+
+                        write(
+                            bytes,
+                            &mut offset,
+                            &[
+                                NON_DUP_MARKER,
+                                is_signer as u8,
+                                is_writable as u8,
+                                account.executable as u8,
+                            ],
+                        );
+                        // Original data length, filled in on deserialization.
+                        write(bytes, &mut offset, &[0; 4]);
+                        write(bytes, &mut offset, key.as_ref());
+                        write(bytes, &mut offset, account.owner.as_ref());
+                        write(bytes, &mut offset, &account.lamports.to_le_bytes());
+                        write(bytes, &mut offset, &(account.data.len() as u64).to_le_bytes());
+                        write(bytes, &mut offset, &account.data);
+
+                        offset += MAX_PERMITTED_DATA_INCREASE;
+                        offset = align(offset);
+
+                        write(bytes, &mut offset, &account.rent_epoch.to_le_bytes());
+                    }
+                    Position::Duplicate(dup_info) => {
+                        write(bytes, &mut offset, &[dup_info]);
+                        offset += 7; // padding
+                    }
+                }
+            }
+
+            write(bytes, &mut offset, &(instruction.data.len() as u64).to_le_bytes());
+            write(bytes, &mut offset, &instruction.data);
+            write(bytes, &mut offset, instruction.program_id.as_ref());
+        }
+
+        input.accounts = serialized;
+
+        let range = input.bytes().as_ptr_range();
+        BUFFERS.with(|buffers| buffers.borrow_mut().push(range.start as usize..range.end as usize));
+
+        input
+    }
+
+    /// Deserializes the input into the program id, accounts and instruction data, as the
+    /// program entrypoint would.
+    pub fn deserialize(&mut self) -> (&Pubkey, Vec<AccountInfo<'_>>, &[u8]) {
+        // SAFETY: The buffer was serialized in the layout expected by `deserialize`, is aligned
+        // to 8 bytes, and remains mutably borrowed for as long as the results are alive.
+        unsafe { deserialize(self.buffer.as_mut_ptr().cast()) }
+    }
+
+    /// Returns the current state of the account with the given key, or `None` if the account is
+    /// not part of the input.
+    pub fn account(&self, key: &Pubkey) -> Option<Account> {
+        let account = self.accounts.iter().find(|account| account.key == *key)?;
+        let bytes = &self.bytes()[account.offset..];
+
+        let data_len = read_u64(bytes, DATA_LEN) as usize;
+
+        Some(Account {
+            lamports: read_u64(bytes, LAMPORTS),
+            data: bytes[DATA..DATA + data_len].to_vec(),
+            owner: Pubkey::try_from(&bytes[OWNER..LAMPORTS]).unwrap(),
+            executable: bytes[EXECUTABLE] != 0,
+            rent_epoch: account.rent_epoch,
+        })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        let len = self.buffer.len() * size_of::<u64>();
+
+        // SAFETY: The buffer is valid for reads of `len` bytes.
+        unsafe { std::slice::from_raw_parts(self.buffer.as_ptr().cast(), len) }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        let len = self.buffer.len() * size_of::<u64>();
+
+        // SAFETY: The buffer is valid for reads and writes of `len` bytes.
+        unsafe { std::slice::from_raw_parts_mut(self.buffer.as_mut_ptr().cast(), len) }
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        let start = self.bytes().as_ptr() as usize;
+        BUFFERS.with(|buffers| buffers.borrow_mut().retain(|range| range.start != start));
+    }
+}
+
+/// Returns `true` if `account` was deserialized from a live [`Input`] on the current thread, so
+/// that the memory around its key and data is laid out as the runtime lays it out.
+///
+/// The data is only checked if it is not mutably borrowed.
+pub(crate) fn is_serialized(account: &AccountInfo) -> bool {
+    let contains = |ptr: *const u8| {
+        BUFFERS.with(|buffers| buffers.borrow().iter().any(|range| range.contains(&(ptr as usize))))
+    };
+
+    let data = account.try_borrow_data().map_or(true, |data| contains(data.as_ptr()));
+
+    data && contains((account.key as *const Pubkey).cast())
+}
+
+/// Returns the serialized length of a non-duplicate account with `data_len` bytes of data.
+fn account_len(data_len: usize) -> usize {
+    align(DATA + data_len + MAX_PERMITTED_DATA_INCREASE) + size_of::<u64>()
+}
+
+fn align(offset: usize) -> usize {
+    (offset + (BPF_ALIGN_OF_U128 - 1)) & !(BPF_ALIGN_OF_U128 - 1)
+}
+
+fn write(bytes: &mut [u8], offset: &mut usize, src: &[u8]) {
+    bytes[*offset..*offset + src.len()].copy_from_slice(src);
+    *offset += src.len();
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + size_of::<u64>()].try_into().unwrap())
+}
@@ -0,0 +1,575 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_program;
+
+use super::system;
+use crate::invoke::InstructionRef;
+use crate::{log, pubkeys_eq};
+
+type Handler = Rc<dyn Fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult>;
+
+thread_local! {
+    static PROGRAMS: RefCell<Vec<(Pubkey, Handler)>> = const { RefCell::new(Vec::new()) };
+    static CALL_STACK: RefCell<Vec<Pubkey>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+}
+
+/// Registers `process` as the entrypoint of the program with the given id, for cross-program
+/// invocations made on the current thread.
+///
+/// Registering a program replaces any previously registered handler for the same id. The system
+/// program is built in and cannot be replaced.
+///
+/// # Example
+///
+/// ```
+/// use solana_program::account_info::AccountInfo;
+/// use solana_program::entrypoint::ProgramResult;
+/// use solana_program::pubkey::Pubkey;
+/// use solana_utils::test_runtime::register_program;
+///
+/// fn process_instruction(
+///     program_id: &Pubkey,
+///     accounts: &[AccountInfo],
+///     instruction_data: &[u8],
+/// ) -> ProgramResult {
+///     Ok(())
+/// }
+///
+/// register_program(Pubkey::new_unique(), process_instruction);
+/// ```
+pub fn register_program<F>(program_id: Pubkey, process: F)
+where
+    F: Fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult + 'static,
+{
+    assert!(!pubkeys_eq(&program_id, &system_program::ID), "cannot replace the system program");
+
+    PROGRAMS.with(|programs| {
+        let mut programs = programs.borrow_mut();
+        let handler: Handler = Rc::new(process);
+
+        match programs.iter_mut().find(|(id, _)| pubkeys_eq(id, &program_id)) {
+            Some((_, current)) => *current = handler,
+            None => programs.push((program_id, handler)),
+        }
+    });
+}
+
+/// Deregisters the handler of the program with the given id on the current thread.
+pub fn deregister_program(program_id: &Pubkey) {
+    PROGRAMS.with(|programs| programs.borrow_mut().retain(|(id, _)| !pubkeys_eq(id, program_id)));
+}
+
+/// Returns the current stack height, as reported by `sol_get_stack_height`.
+pub(crate) fn stack_height() -> u64 {
+    CALL_STACK.with(|stack| stack.borrow().len() as u64)
+}
+
+/// Returns the current return data, as reported by `sol_get_return_data`.
+pub(crate) fn return_data() -> Option<(Pubkey, Vec<u8>)> {
+    RETURN_DATA.with(|return_data| return_data.borrow().clone())
+}
+
+/// Sets the return data on behalf of the executing program, as done by `sol_set_return_data`.
+pub(crate) fn set_return_data(data: &[u8]) {
+    let program_id = CALL_STACK.with(|stack| stack.borrow().last().copied()).unwrap_or_default();
+    let return_data = if data.is_empty() { None } else { Some((program_id, data.to_vec())) };
+
+    RETURN_DATA.with(|current| *current.borrow_mut() = return_data);
+}
+
+/// A frame of the call stack, popped when dropped.
+pub(crate) struct Frame(());
+
+impl Frame {
+    /// Pushes a frame for `program_id`, clearing the return data as the runtime does when a
+    /// program is invoked.
+    pub(crate) fn push(program_id: &Pubkey) -> Frame {
+        CALL_STACK.with(|stack| stack.borrow_mut().push(*program_id));
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = None);
+        Frame(())
+    }
+}
+
+impl Drop for Frame {
+    fn drop(&mut self) {
+        CALL_STACK.with(|stack| stack.borrow_mut().pop());
+    }
+}
+
+/// Invokes a cross-program instruction in the test runtime.
+pub(crate) fn invoke_signed(
+    instruction: &InstructionRef,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let signers = pda_signers(signers_seeds)?;
+
+    let mut accounts: Vec<AccountInfo> = Vec::with_capacity(instruction.accounts.len());
+
+    for account_meta in instruction.accounts.iter() {
+        // Privileges of accounts referenced more than once are merged, as on-chain.
+        let (is_signer, is_writable) = instruction
+            .accounts
+            .iter()
+            .filter(|other| pubkeys_eq(&other.pubkey, &account_meta.pubkey))
+            .fold((false, false), |(is_signer, is_writable), other| {
+                (is_signer || other.is_signer, is_writable || other.is_writable)
+            });
+
+        let account_info =
+            account_infos.iter().find(|info| pubkeys_eq(info.key, &account_meta.pubkey));
+
+        let mut account_info = match account_info {
+            Some(account_info) => account_info.clone(),
+            None => {
+                log!("Error: Instruction references an unknown account {}", account_meta.pubkey);
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+        };
+
+        if is_writable && !account_info.is_writable {
+            log!("Error: {}'s writable privilege escalated", account_info.key);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        if is_signer
+            && !account_info.is_signer
+            && !signers.iter().any(|signer| pubkeys_eq(signer, account_info.key))
+        {
+            log!("Error: {}'s signer privilege escalated", account_info.key);
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        account_info.is_signer = is_signer;
+        account_info.is_writable = is_writable;
+
+        accounts.push(account_info);
+    }
+
+    let program_id = instruction.program_id;
+
+    let handler = if pubkeys_eq(program_id, &system_program::ID) {
+        None
+    } else {
+        let handler = PROGRAMS.with(|programs| {
+            let programs = programs.borrow();
+            programs.iter().find(|(id, _)| pubkeys_eq(id, program_id)).map(|(_, h)| h.clone())
+        });
+
+        match handler {
+            Some(handler) => Some(handler),
+            None => {
+                log!("Error: Program {} is not registered with the test runtime", program_id);
+                return Err(ProgramError::IncorrectProgramId);
+            }
+        }
+    };
+
+    let pre_accounts = PreAccount::snapshot(&accounts)?;
+
+    {
+        let _frame = Frame::push(program_id);
+
+        match handler {
+            Some(handler) => handler(program_id, &accounts, instruction.data)?,
+            None => system::process(&accounts, instruction.data)?,
+        }
+    }
+
+    verify(program_id, &pre_accounts)
+}
+
+/// The state of an account before an invocation, against which the changes made by the callee
+/// are checked.
+struct PreAccount<'a, 'info> {
+    account: &'a AccountInfo<'info>,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+}
+
+impl<'a, 'info> PreAccount<'a, 'info> {
+    /// Records the state of each unique account of an invocation.
+    fn snapshot(accounts: &'a [AccountInfo<'info>]) -> Result<Vec<Self>, ProgramError> {
+        let mut pre_accounts: Vec<PreAccount> = Vec::with_capacity(accounts.len());
+
+        for account in accounts {
+            if pre_accounts.iter().any(|pre| pubkeys_eq(pre.account.key, account.key)) {
+                continue;
+            }
+
+            pre_accounts.push(PreAccount {
+                account,
+                lamports: **account.try_borrow_lamports()?,
+                data: account.try_borrow_data()?.to_vec(),
+                owner: *account.owner,
+            });
+        }
+
+        Ok(pre_accounts)
+    }
+}
+
+/// Checks the changes made by `program_id` to the accounts of an invocation, as the runtime does
+/// once the callee returns.
+fn verify(program_id: &Pubkey, pre_accounts: &[PreAccount]) -> ProgramResult {
+    let mut pre_sum = 0u128;
+    let mut post_sum = 0u128;
+
+    for pre in pre_accounts {
+        let account = pre.account;
+        let lamports = **account.try_borrow_lamports()?;
+        let data = account.try_borrow_data()?;
+        let is_owner = pubkeys_eq(&pre.owner, program_id);
+
+        if !pubkeys_eq(account.owner, &pre.owner)
+            && (!account.is_writable
+                || account.executable
+                || !is_owner
+                || data.iter().any(|byte| *byte != 0))
+        {
+            return Err(violation(account, "illegally modified the owner of"));
+        }
+
+        if lamports != pre.lamports {
+            if !account.is_writable {
+                return Err(violation(account, "changed the balance of read-only account"));
+            }
+            if !is_owner && lamports < pre.lamports {
+                return Err(violation(account, "spent from the balance of unowned account"));
+            }
+        }
+
+        if data[..] != pre.data[..] {
+            if !account.is_writable {
+                return Err(violation(account, "modified the data of read-only account"));
+            }
+            if !is_owner {
+                return Err(violation(account, "modified the data of unowned account"));
+            }
+        }
+
+        pre_sum += pre.lamports as u128;
+        post_sum += lamports as u128;
+    }
+
+    if pre_sum != post_sum {
+        log!(
+            "Error: Instruction changed the sum of the account balances from {} to {}",
+            pre_sum,
+            post_sum,
+        );
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+#[cold]
+fn violation(account: &AccountInfo, change: &str) -> ProgramError {
+    log!("Error: Instruction {} {}", change, account.key);
+    ProgramError::InvalidAccountData
+}
+
+/// Derives the addresses signed for by the executing program with `signers_seeds`.
+fn pda_signers(signers_seeds: &[&[&[u8]]]) -> Result<Vec<Pubkey>, ProgramError> {
+    if signers_seeds.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let caller = match CALL_STACK.with(|stack| stack.borrow().last().copied()) {
+        Some(caller) => caller,
+        None => {
+            log!("Error: Cannot sign with seeds outside of an executing program");
+            return Err(ProgramError::InvalidSeeds);
+        }
+    };
+
+    let mut signers = Vec::with_capacity(signers_seeds.len());
+
+    for seeds in signers_seeds {
+        match Pubkey::create_program_address(seeds, &caller) {
+            Ok(signer) => signers.push(signer),
+            Err(err) => {
+                log!("Error: Invalid signer seeds for program {}: {}", caller, err);
+                return Err(err.into());
+            }
+        }
+    }
+
+    Ok(signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_program::instruction::{AccountMeta, Instruction};
+
+    use super::*;
+    use crate::invoke::invoke_signed;
+    use crate::test_runtime::{process_instruction, Account};
+
+    /// A caller program invoking a callee program, with accounts owned by either.
+    struct Test {
+        caller: Pubkey,
+        callee: Pubkey,
+        signer: Pubkey,
+        owned: Pubkey,
+        readonly: Pubkey,
+        unowned: Pubkey,
+        pda: Pubkey,
+        bump: u8,
+    }
+
+    impl Test {
+        fn new() -> Test {
+            let caller = Pubkey::new_unique();
+            let (pda, bump) = Pubkey::find_program_address(&[b"pda"], &caller);
+
+            Test {
+                caller,
+                callee: Pubkey::new_unique(),
+                signer: Pubkey::new_unique(),
+                owned: Pubkey::new_unique(),
+                readonly: Pubkey::new_unique(),
+                unowned: Pubkey::new_unique(),
+                pda,
+                bump,
+            }
+        }
+
+        /// Invokes `program_id` from the caller with `metas`, with `process` registered as the
+        /// callee. Returns the result and the states of the accounts afterwards.
+        fn invoke(
+            &self,
+            program_id: &Pubkey,
+            metas: Vec<AccountMeta>,
+            signers_seeds: &[&[&[u8]]],
+            process: impl Fn(&[AccountInfo]) -> ProgramResult + 'static,
+        ) -> (ProgramResult, Vec<(Pubkey, Account)>) {
+            register_program(self.callee, move |_, accounts, _| process(accounts));
+
+            let instruction = Instruction::new_with_bytes(
+                self.caller,
+                &[],
+                vec![
+                    AccountMeta::new(self.signer, true),
+                    AccountMeta::new(self.owned, false),
+                    AccountMeta::new_readonly(self.readonly, false),
+                    AccountMeta::new(self.unowned, false),
+                    AccountMeta::new_readonly(self.pda, false),
+                ],
+            );
+
+            let account = |owner: &Pubkey| Account {
+                data: vec![1, 2, 3, 4],
+                ..Account::new(1_000, 0, owner)
+            };
+            let mut accounts = vec![
+                (self.signer, Account::new(1_000, 0, &system_program::ID)),
+                (self.owned, account(&self.callee)),
+                (self.readonly, account(&self.callee)),
+                (self.unowned, account(&self.caller)),
+                (self.pda, Account::new(1_000, 0, &system_program::ID)),
+            ];
+
+            let cpi = Instruction::new_with_bytes(*program_id, &[], metas);
+            let result = process_instruction(&instruction, &mut accounts, |_, accounts, _| {
+                invoke_signed(&cpi, accounts, signers_seeds)
+            });
+
+            (result, accounts)
+        }
+
+        /// Invokes the callee with the owned, read-only and unowned accounts.
+        fn invoke_callee(
+            &self,
+            process: impl Fn(&[AccountInfo]) -> ProgramResult + 'static,
+        ) -> (ProgramResult, Vec<(Pubkey, Account)>) {
+            let metas = vec![
+                AccountMeta::new(self.owned, false),
+                AccountMeta::new_readonly(self.readonly, false),
+                AccountMeta::new(self.unowned, false),
+            ];
+
+            self.invoke(&self.callee, metas, &[], process)
+        }
+    }
+
+    fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) {
+        **from.lamports.borrow_mut() -= amount;
+        **to.lamports.borrow_mut() += amount;
+    }
+
+    #[test]
+    fn pda_signer() {
+        let test = Test::new();
+        let metas = vec![AccountMeta::new_readonly(test.pda, true)];
+
+        let (result, _) =
+            test.invoke(&test.callee, metas, &[&[b"pda", &[test.bump]]], |accounts| {
+                assert!(accounts[0].is_signer);
+                Ok(())
+            });
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn pda_signer_bad_seeds() {
+        let test = Test::new();
+        let metas = || vec![AccountMeta::new_readonly(test.pda, true)];
+        let (_, other_bump) = Pubkey::find_program_address(&[b"other"], &test.caller);
+
+        // The seeds derive another address.
+        let seeds: &[&[&[u8]]] = &[&[b"other", &[other_bump]]];
+        let (result, _) = test.invoke(&test.callee, metas(), seeds, |_| Ok(()));
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+
+        let seeds: &[&[&[u8]]] = &[&[&[0; 33]]];
+        let (result, _) = test.invoke(&test.callee, metas(), seeds, |_| Ok(()));
+        assert_eq!(result, Err(ProgramError::MaxSeedLengthExceeded));
+    }
+
+    #[test]
+    fn privilege_escalation() {
+        let test = Test::new();
+
+        let metas = vec![AccountMeta::new(test.readonly, false)];
+        let (result, _) = test.invoke(&test.callee, metas, &[], |_| Ok(()));
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+
+        let metas = vec![AccountMeta::new(test.owned, true)];
+        let (result, _) = test.invoke(&test.callee, metas, &[], |_| Ok(()));
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn unknown_account() {
+        let test = Test::new();
+
+        let metas = vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)];
+        let (result, _) = test.invoke(&test.callee, metas, &[], |_| Ok(()));
+        assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+    }
+
+    #[test]
+    fn unregistered_program() {
+        let test = Test::new();
+
+        let (result, _) = test.invoke(&Pubkey::new_unique(), vec![], &[], |_| Ok(()));
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+    }
+
+    #[test]
+    fn merged_duplicates() {
+        let test = Test::new();
+        let metas = vec![
+            AccountMeta::new_readonly(test.owned, false),
+            AccountMeta::new(test.owned, false),
+            AccountMeta::new_readonly(test.signer, false),
+            AccountMeta::new_readonly(test.signer, true),
+        ];
+
+        let (result, _) = test.invoke(&test.callee, metas, &[], |accounts| {
+            let privileges: Vec<_> =
+                accounts.iter().map(|account| (account.is_signer, account.is_writable)).collect();
+            assert_eq!(privileges, [(false, true), (false, true), (true, false), (true, false)]);
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn allowed_changes() {
+        let test = Test::new();
+
+        let (result, accounts) = test.invoke_callee(|accounts| {
+            let [owned, _, unowned] = accounts else { unreachable!() };
+
+            // A program may credit any writable account from the accounts it owns, and reassign
+            // the accounts it owns once their data is zeroed.
+            move_lamports(owned, unowned, 100);
+            owned.try_borrow_mut_data()?.fill(0);
+            owned.assign(&system_program::ID);
+            Ok(())
+        });
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(accounts[1].1, Account::new(900, 4, &system_program::ID));
+        assert_eq!(accounts[3].1.lamports, 1_100);
+    }
+
+    #[test]
+    fn lamport_changes() {
+        let test = Test::new();
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            let [owned, _, unowned] = accounts else { unreachable!() };
+            move_lamports(unowned, owned, 1);
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            let [owned, readonly, _] = accounts else { unreachable!() };
+            move_lamports(readonly, owned, 1);
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            **accounts[0].lamports.borrow_mut() += 1;
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn data_changes() {
+        let test = Test::new();
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            accounts[2].try_borrow_mut_data()?[0] = 0;
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            accounts[1].try_borrow_mut_data()?[0] = 0;
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn owner_changes() {
+        let test = Test::new();
+
+        // The data of the account is not zeroed.
+        let (result, _) = test.invoke_callee(|accounts| {
+            accounts[0].assign(&system_program::ID);
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            accounts[1].try_borrow_mut_data()?.fill(0);
+            accounts[1].assign(&system_program::ID);
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let (result, _) = test.invoke_callee(|accounts| {
+            accounts[2].assign(&system_program::ID);
+            Ok(())
+        });
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+}
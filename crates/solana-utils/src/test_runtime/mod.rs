@@ -0,0 +1,130 @@
+//! A simulated runtime for exercising program logic in host tests.
+//!
+//! Accounts are serialized in the same layout the runtime uses for program input, so the
+//! [`AccountInfo`]s handed to a program can be reallocated, reassigned and closed exactly as they
+//! would be on-chain. Helpers that access the memory around an [`AccountInfo`], such as
+//! [`realloc_account_mut`](crate::realloc_account_mut), still panic for accounts that were not
+//! deserialized by the test runtime. Sysvars are provided through the syscall stubs of
+//! `solana_program`.
+//!
+//! Cross-program invocations are routed to in-process handlers: the system program is emulated,
+//! and other programs can be registered with [`register_program`]. Signer and writable privileges
+//! are checked as on-chain, with program-derived signers verified against the signer seeds and
+//! the id of the calling program. Once the callee returns, its changes are checked as on-chain:
+//! it may only change the data and debit the lamports of writable accounts it owns, reassign
+//! writable accounts it owns whose data is zeroed, and credit writable accounts, while the sum of
+//! the lamports must stay the same. Violations fail with `ProgramError::InvalidAccountData`.
+//! Return data is tracked as on-chain, and cleared whenever a program is invoked.
+//!
+//! # Example
+//!
+//! ```
+//! use solana_program::instruction::{AccountMeta, Instruction};
+//! use solana_program::pubkey::Pubkey;
+//! use solana_program::system_program;
+//! use solana_utils::test_runtime::{process_instruction, Account};
+//!
+//! let program_id = Pubkey::new_unique();
+//! let payer = Pubkey::new_unique();
+//! let account = Pubkey::new_unique();
+//!
+//! let instruction = Instruction::new_with_bytes(program_id, &[], vec![
+//!     AccountMeta::new(account, true),
+//!     AccountMeta::new(payer, true),
+//!     AccountMeta::new_readonly(system_program::ID, false),
+//! ]);
+//!
+//! let mut accounts = vec![
+//!     (account, Account::default()),
+//!     (payer, Account::new(1_000_000_000, 0, &system_program::ID)),
+//!     (system_program::ID, Account::default()),
+//! ];
+//!
+//! process_instruction(&instruction, &mut accounts, |program_id, accounts, _data| {
+//!     let [account, payer, system_program] = accounts else { unreachable!() };
+//!
+//!     solana_utils::create_or_allocate_account(account, payer, system_program, 8, program_id, &[])
+//! })
+//! .unwrap();
+//!
+//! assert_eq!(accounts[0].1.owner, program_id);
+//! assert_eq!(accounts[0].1.data, [0; 8]);
+//! ```
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::instruction::Instruction;
+use solana_program::pubkey::Pubkey;
+
+mod input;
+mod invoke;
+mod stubs;
+mod system;
+
+pub use self::input::Input;
+pub use self::invoke::{deregister_program, register_program};
+pub use self::stubs::{set_sysvars, sysvars, Sysvars};
+
+pub(crate) use self::input::is_serialized;
+pub(crate) use self::invoke::invoke_signed;
+
+/// The state of an account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    /// Lamports in the account.
+    pub lamports: u64,
+    /// Data held in the account.
+    pub data: Vec<u8>,
+    /// The program that owns the account.
+    pub owner: Pubkey,
+    /// Whether the account contains a program (and is strictly read-only).
+    pub executable: bool,
+    /// The epoch at which the account will next owe rent.
+    pub rent_epoch: u64,
+}
+
+impl Account {
+    /// Creates an account holding `lamports` with `space` bytes of zeroed data.
+    pub fn new(lamports: u64, space: usize, owner: &Pubkey) -> Account {
+        Account { lamports, data: vec![0; space], owner: *owner, executable: false, rent_epoch: 0 }
+    }
+}
+
+/// Processes `instruction` with `process` as the program entrypoint.
+///
+/// The states of the accounts referenced by the instruction are taken from `accounts`, and are
+/// written back if the instruction succeeds. As on-chain, changes made by a failed instruction
+/// are discarded.
+///
+/// # Panics
+///
+/// Panics if the state of an account referenced by the instruction is missing.
+pub fn process_instruction<F>(
+    instruction: &Instruction,
+    accounts: &mut [(Pubkey, Account)],
+    process: F,
+) -> ProgramResult
+where
+    F: FnOnce(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult,
+{
+    stubs::install();
+
+    let mut input = Input::new(instruction, accounts);
+
+    let result = {
+        let (program_id, account_infos, data) = input.deserialize();
+        let _frame = invoke::Frame::push(program_id);
+
+        process(program_id, &account_infos, data)
+    };
+
+    if result.is_ok() {
+        for (key, account) in accounts.iter_mut() {
+            if let Some(state) = input.account(key) {
+                *account = state;
+            }
+        }
+    }
+
+    result
+}
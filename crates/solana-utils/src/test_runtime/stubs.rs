@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+use std::sync::Once;
+
+use solana_program::account_info::AccountInfo;
+use solana_program::clock::Clock;
+use solana_program::entrypoint::{ProgramResult, SUCCESS};
+use solana_program::epoch_schedule::EpochSchedule;
+use solana_program::instruction::Instruction;
+use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use solana_program::pubkey::Pubkey;
+use solana_program::rent::Rent;
+
+/// Sysvars provided to programs by the test runtime.
+///
+/// Sysvars are configured per thread, so tests running in parallel do not affect each other.
+#[derive(Clone, Debug, Default)]
+pub struct Sysvars {
+    pub clock: Clock,
+    pub epoch_schedule: EpochSchedule,
+    pub rent: Rent,
+}
+
+thread_local! {
+    static SYSVARS: RefCell<Sysvars> = RefCell::new(Sysvars::default());
+}
+
+/// Returns the sysvars provided to programs on the current thread.
+pub fn sysvars() -> Sysvars {
+    SYSVARS.with(|sysvars| sysvars.borrow().clone())
+}
+
+/// Sets the sysvars provided to programs on the current thread.
+pub fn set_sysvars(sysvars: Sysvars) {
+    install();
+
+    SYSVARS.with(|current| *current.borrow_mut() = sysvars);
+}
+
+/// Installs the test runtime syscall stubs, if not already installed.
+pub(crate) fn install() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+}
+
+struct TestSyscallStubs;
+
+impl TestSyscallStubs {
+    fn get_sysvar<T: Clone>(var_addr: *mut u8, get: impl FnOnce(&Sysvars) -> &T) -> u64 {
+        SYSVARS.with(|sysvars| {
+            let sysvars = sysvars.borrow();
+
+            // SAFETY: `var_addr` points to a valid, aligned instance of `T`.
+            unsafe { *var_addr.cast::<T>() = get(&sysvars).clone() };
+        });
+
+        SUCCESS
+    }
+}
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        super::invoke::invoke_signed(&instruction.into(), account_infos, signers_seeds)
+    }
+
+    fn sol_get_stack_height(&self) -> u64 {
+        super::invoke::stack_height()
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        super::invoke::return_data()
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        super::invoke::set_return_data(data)
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        Self::get_sysvar(var_addr, |sysvars| &sysvars.clock)
+    }
+
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        Self::get_sysvar(var_addr, |sysvars| &sysvars.epoch_schedule)
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        Self::get_sysvar(var_addr, |sysvars| &sysvars.rent)
+    }
+}
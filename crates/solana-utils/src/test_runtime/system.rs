@@ -0,0 +1,242 @@
+//! Emulation of the system program.
+
+use solana_program::account_info::AccountInfo;
+use solana_program::entrypoint::ProgramResult;
+use solana_program::program_error::ProgramError;
+use solana_program::program_utils::limited_deserialize;
+use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::{
+    SystemError, SystemInstruction, MAX_PERMITTED_DATA_LENGTH,
+};
+use solana_program::system_program;
+
+use crate::{log, pubkeys_eq};
+
+/// Maximum over-the-wire size of a transaction, used to limit instruction deserialization.
+const PACKET_DATA_SIZE: u64 = 1280 - 40 - 8;
+
+/// Processes a system program instruction.
+pub(crate) fn process(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction = limited_deserialize(data, PACKET_DATA_SIZE)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    match instruction {
+        SystemInstruction::CreateAccount { lamports, space, owner } => {
+            let [from, to, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            create_account(from, to, to.key, lamports, space, &owner, to.is_signer)
+        }
+        SystemInstruction::CreateAccountWithSeed { base, seed, lamports, space, owner } => {
+            let [from, to, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            let address = address_with_seed(to.key, &base, &seed, &owner)?;
+            create_account(from, to, &address, lamports, space, &owner, is_signer(accounts, &base))
+        }
+        SystemInstruction::Assign { owner } => {
+            let [account, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            assign(account, account.key, &owner, account.is_signer)
+        }
+        SystemInstruction::AssignWithSeed { base, seed, owner } => {
+            let [account, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            let address = address_with_seed(account.key, &base, &seed, &owner)?;
+            assign(account, &address, &owner, is_signer(accounts, &base))
+        }
+        SystemInstruction::Transfer { lamports } => {
+            let [from, to, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            if !from.is_signer {
+                log!("Transfer: `from` account {} must sign", from.key);
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            transfer(from, to, lamports)
+        }
+        SystemInstruction::TransferWithSeed { lamports, from_seed, from_owner } => {
+            let [from, base, to, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            if !base.is_signer {
+                log!("Transfer: `from` account {} must sign", base.key);
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            address_with_seed(from.key, base.key, &from_seed, &from_owner)?;
+            transfer(from, to, lamports)
+        }
+        SystemInstruction::Allocate { space } => {
+            let [account, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            allocate(account, account.key, space, account.is_signer)
+        }
+        SystemInstruction::AllocateWithSeed { base, seed, space, owner } => {
+            let [account, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            let address = address_with_seed(account.key, &base, &seed, &owner)?;
+            let is_signer = is_signer(accounts, &base);
+
+            allocate(account, &address, space, is_signer)?;
+            assign(account, &address, &owner, is_signer)
+        }
+        _ => {
+            log!(
+                "Error: System instruction {:?} is not supported by the test runtime",
+                instruction
+            );
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+}
+
+fn create_account(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    address: &Pubkey,
+    lamports: u64,
+    space: u64,
+    owner: &Pubkey,
+    is_signer: bool,
+) -> ProgramResult {
+    // If it looks like the `to` account is already in use, bail.
+    if to.lamports() > 0 {
+        log!("Create Account: account {} already in use", address);
+        return Err(system_error(SystemError::AccountAlreadyInUse));
+    }
+
+    allocate(to, address, space, is_signer)?;
+    assign(to, address, owner, is_signer)?;
+
+    if !from.is_signer {
+        log!("Transfer: `from` account {} must sign", from.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    transfer(from, to, lamports)
+}
+
+fn allocate(account: &AccountInfo, address: &Pubkey, space: u64, is_signer: bool) -> ProgramResult {
+    if !is_signer {
+        log!("Allocate: 'to' account {} must sign", address);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // If it looks like the account is already in use, bail.
+    if !account.data_is_empty() || !pubkeys_eq(account.owner, &system_program::ID) {
+        log!("Allocate: account {} already in use", address);
+        return Err(system_error(SystemError::AccountAlreadyInUse));
+    }
+
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        log!("Allocate: requested {}, max allowed {}", space, MAX_PERMITTED_DATA_LENGTH);
+        return Err(system_error(SystemError::InvalidAccountDataLength));
+    }
+
+    check_writable(account)?;
+
+    account.realloc(space as usize, true)
+}
+
+fn assign(
+    account: &AccountInfo,
+    address: &Pubkey,
+    owner: &Pubkey,
+    is_signer: bool,
+) -> ProgramResult {
+    // No work to do, just return.
+    if pubkeys_eq(account.owner, owner) {
+        return Ok(());
+    }
+
+    if !is_signer {
+        log!("Assign: account {} must sign", address);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_writable(account)?;
+
+    account.assign(owner);
+
+    Ok(())
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.data_is_empty() {
+        log!("Transfer: `from` must not carry data");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if lamports > from.lamports() {
+        log!("Transfer: insufficient lamports {}, need {}", from.lamports(), lamports);
+        return Err(system_error(SystemError::ResultWithNegativeLamports));
+    }
+
+    if !pubkeys_eq(from.owner, &system_program::ID) {
+        log!("Transfer: `from` account {} is not owned by the system program", from.key);
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    check_writable(from)?;
+    check_writable(to)?;
+
+    // Transferring to the same account is a no-op.
+    if pubkeys_eq(from.key, to.key) {
+        return Ok(());
+    }
+
+    let mut from_lamports = from.try_borrow_mut_lamports()?;
+    let mut to_lamports = to.try_borrow_mut_lamports()?;
+
+    **from_lamports -= lamports;
+    **to_lamports = to_lamports.checked_add(lamports).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+fn check_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        log!("Error: Account {} is not writable", account.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+fn is_signer(accounts: &[AccountInfo], key: &Pubkey) -> bool {
+    accounts.iter().any(|account| account.is_signer && pubkeys_eq(account.key, key))
+}
+
+fn address_with_seed(
+    address: &Pubkey,
+    base: &Pubkey,
+    seed: &str,
+    owner: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    let expected = Pubkey::create_with_seed(base, seed, owner)
+        .map_err(|_| system_error(SystemError::MaxSeedLengthExceeded))?;
+
+    if !pubkeys_eq(address, &expected) {
+        log!("Create: address {} does not match derived address {}", address, expected);
+        return Err(system_error(SystemError::AddressWithSeedMismatch));
+    }
+
+    Ok(*address)
+}
+
+fn system_error(error: SystemError) -> ProgramError {
+    ProgramError::Custom(error as u32)
+}
@@ -1,7 +1,16 @@
-pub use solana_utils_macro::VariantName;
+use solana_program::pubkey::Pubkey;
+
+pub use solana_utils_macro::{Discriminator, VariantName};
 
 /// This trait provides enums with a method to return the name the current variant.
 ///
+/// Variant names can be changed with `#[variant_name(rename = "...")]` on a variant, or with
+/// `#[variant_name(rename_all = "...")]` on the enum, which accepts the same case conventions as
+/// serde: `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`,
+/// `SCREAMING_SNAKE_CASE`, `kebab-case` and `SCREAMING-KEBAB-CASE`.
+///
+/// The derive macro also implements [`VariantNames`].
+///
 /// # Example
 ///
 /// ```
@@ -17,8 +26,188 @@ pub use solana_utils_macro::VariantName;
 /// assert_eq!(Enum::Foo.variant_name(), "Foo");
 /// assert_eq!(Enum::Bar().variant_name(), "Bar");
 /// assert_eq!(Enum::Baz {}.variant_name(), "Baz");
+///
+/// #[derive(VariantName)]
+/// #[variant_name(rename_all = "snake_case")]
+/// enum Renamed {
+///     FooBar,
+///     #[variant_name(rename = "qux")]
+///     Baz {},
+/// }
+///
+/// assert_eq!(Renamed::FooBar.variant_name(), "foo_bar");
+/// assert_eq!(Renamed::Baz {}.variant_name(), "qux");
 /// ```
 pub trait VariantName {
     /// Returns the name of the enum variant.
     fn variant_name(&self) -> &'static str;
 }
+
+/// This trait provides enums with the names and indices of all their variants.
+///
+/// It is implemented by `#[derive(VariantName)]`, with the names given by [`VariantName`].
+///
+/// # Example
+///
+/// ```
+/// use solana_utils::{VariantName, VariantNames};
+///
+/// #[derive(VariantName, Debug, PartialEq)]
+/// #[variant_name(rename_all = "snake_case")]
+/// enum Enum {
+///     FooBar,
+///     Bar(u8),
+///     #[variant_name(rename = "qux")]
+///     Baz {},
+/// }
+///
+/// assert_eq!(Enum::VARIANT_NAMES, ["foo_bar", "bar", "qux"]);
+/// assert_eq!(Enum::Bar(1).variant_index(), 1);
+/// assert_eq!(Enum::from_variant_name("qux"), Some(Enum::Baz {}));
+/// assert_eq!(Enum::from_variant_name("bar"), None);
+/// ```
+pub trait VariantNames: VariantName {
+    /// The names of the variants, in declaration order.
+    const VARIANT_NAMES: &'static [&'static str];
+
+    /// Returns the index of the enum variant in declaration order.
+    fn variant_index(&self) -> usize;
+
+    /// Returns the variant named `name`, if it has no fields.
+    fn from_variant_name(name: &str) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+/// The length of a [`Discriminator`] in bytes.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// An 8-byte prefix that identifies the type of account data, instruction data or events.
+///
+/// The derive macro computes the discriminator at compile time as Anchor does, from the first 8
+/// bytes of the sha256 hash of `"<namespace>:<name>"`. The namespace defaults to `account`, and
+/// the name to the name of the type. The `global` namespace, which Anchor uses for instructions,
+/// converts the name of the type to snake_case as Anchor does, keeping acronyms as one word, so
+/// `SetURI` becomes `set_uri`. Both can be changed with
+/// `#[discriminator(namespace = "...", name = "...")]`, or the discriminator can be given with
+/// `#[discriminator(bytes = ...)]`, either as a byte string literal or an array.
+///
+/// # Example
+///
+/// ```
+/// use solana_utils::Discriminator;
+///
+/// #[derive(Discriminator)]
+/// struct Vault {
+///     amount: u64,
+/// }
+///
+/// // The discriminator of the `deposit` instruction of an Anchor program.
+/// #[derive(Discriminator)]
+/// #[discriminator(namespace = "global")]
+/// struct Deposit {
+///     amount: u64,
+/// }
+///
+/// // The discriminator of the `set_uri` instruction.
+/// #[derive(Discriminator)]
+/// #[discriminator(namespace = "global")]
+/// struct SetURI;
+///
+/// #[derive(Discriminator)]
+/// #[discriminator(bytes = b"legacy__")]
+/// struct Legacy;
+///
+/// assert_eq!(Vault::DISCRIMINATOR, [211, 8, 232, 43, 2, 152, 117, 119]);
+/// assert_eq!(Deposit::DISCRIMINATOR, [242, 35, 198, 137, 82, 225, 242, 182]);
+/// assert_eq!(SetURI::DISCRIMINATOR, [72, 22, 136, 186, 78, 5, 136, 229]);
+/// assert!(Legacy::check_discriminator(b"legacy__ data"));
+/// assert!(!Legacy::check_discriminator(b"legacy"));
+/// ```
+pub trait Discriminator {
+    /// The discriminator of the type.
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN];
+
+    /// Checks whether `data` starts with the discriminator of the type, using `sol_memcmp`.
+    #[inline]
+    fn check_discriminator(data: &[u8]) -> bool {
+        match data.get(..DISCRIMINATOR_LEN) {
+            Some(prefix) => crate::bytes_eq(prefix, &Self::DISCRIMINATOR),
+            None => false,
+        }
+    }
+}
+
+/// Equality checks that use the `sol_memcmp` syscall on-chain.
+///
+/// This is intended for fixed-size byte types, such as pubkeys, hashes and discriminators. Newtypes
+/// around byte arrays can implement it by delegating to the inner array.
+///
+/// # Example
+///
+/// ```
+/// use solana_utils::FastEq;
+///
+/// struct Discriminator([u8; 8]);
+///
+/// impl FastEq for Discriminator {
+///     fn fast_eq(&self, other: &Self) -> bool {
+///         self.0.fast_eq(&other.0)
+///     }
+/// }
+///
+/// assert!(Discriminator([1; 8]).fast_eq(&Discriminator([1; 8])));
+/// assert!(Discriminator([1; 8]).fast_ne(&Discriminator([2; 8])));
+/// ```
+pub trait FastEq {
+    /// Returns whether `self` and `other` are equal.
+    fn fast_eq(&self, other: &Self) -> bool;
+
+    /// Returns whether `self` and `other` are not equal.
+    #[inline]
+    fn fast_ne(&self, other: &Self) -> bool {
+        !self.fast_eq(other)
+    }
+}
+
+impl FastEq for [u8] {
+    #[inline]
+    fn fast_eq(&self, other: &Self) -> bool {
+        crate::bytes_eq(self, other)
+    }
+}
+
+impl<const N: usize> FastEq for [u8; N] {
+    #[inline]
+    fn fast_eq(&self, other: &Self) -> bool {
+        crate::bytes_eq(self, other)
+    }
+}
+
+impl FastEq for Pubkey {
+    #[inline]
+    fn fast_eq(&self, other: &Self) -> bool {
+        crate::pubkeys_eq(self, other)
+    }
+}
+
+impl FastEq for solana_program::hash::Hash {
+    #[inline]
+    fn fast_eq(&self, other: &Self) -> bool {
+        crate::bytes_eq(self.as_ref(), other.as_ref())
+    }
+}
+
+impl FastEq for solana_program::keccak::Hash {
+    #[inline]
+    fn fast_eq(&self, other: &Self) -> bool {
+        self.0.fast_eq(&other.0)
+    }
+}
+
+impl FastEq for solana_program::blake3::Hash {
+    #[inline]
+    fn fast_eq(&self, other: &Self) -> bool {
+        self.0.fast_eq(&other.0)
+    }
+}